@@ -7,28 +7,78 @@ use halo2_base::{
     QuantumCell::{Constant, Existing, Witness},
 };
 use serde::{Deserialize, Serialize};
-use std::env::var;
+use std::fmt;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CircuitInput {
     pub a: String,
     pub b: String,
+    // number of terms computed, `out` being the last one; it fixes the shape of the circuit
+    pub fibonacci_number: usize,
     pub out: String,
 }
 
-// this algorithm takes a public input x, computes x^2 + 72, and outputs the result as public output
+// error returned when the input file does not describe a valid statement
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InputError {
+    // the sequence starts with a and b, so it has at least 2 terms
+    TooFewTerms(usize),
+    InvalidFieldElement { name: &'static str, value: String },
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooFewTerms(n) => {
+                write!(f, "fibonacci_number must be at least 2, got {}", n)
+            }
+            Self::InvalidFieldElement { name, value } => {
+                write!(f, "{} is not a field element: {:?}", name, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+// the input with its field elements deserialized
+#[derive(Clone, Debug)]
+pub struct ParsedInput<F: ScalarField> {
+    pub a: F,
+    pub b: F,
+    pub fibonacci_number: usize,
+    pub out: F,
+}
+
+impl CircuitInput {
+    pub fn parse<F: ScalarField>(&self) -> Result<ParsedInput<F>, InputError> {
+        let field = |name: &'static str, value: &String| {
+            F::from_str_vartime(value)
+                .ok_or_else(|| InputError::InvalidFieldElement { name, value: value.clone() })
+        };
+
+        if self.fibonacci_number < 2 {
+            return Err(InputError::TooFewTerms(self.fibonacci_number));
+        }
+
+        Ok(ParsedInput {
+            a: field("a", &self.a)?,
+            b: field("b", &self.b)?,
+            fibonacci_number: self.fibonacci_number,
+            out: field("out", &self.out)?,
+        })
+    }
+}
+
+// this algorithm takes the public seeds a and b, adds up fibonacci_number terms from them, and
+// constrains the public output out to the last one
 pub fn compute_fibonacci<F: ScalarField>(
     ctx: &mut Context<F>,
     input: CircuitInput,
     make_public: &mut Vec<AssignedValue<F>>,
 ) {
-    let a = F::from_str_vartime(&input.a).expect("deserialize field element should not fail");
-    let b = F::from_str_vartime(&input.b).expect("deserialize field element should not fail");
-    let fibonacci_number = var("FIBONACCI_NUMBER")
-        .unwrap_or_else(|_| panic!("fibonacci_number not set"))
-        .parse()
-        .unwrap();
-    let out = F::from_str_vartime(&input.out).expect("deserialize field element should not fail");
+    let ParsedInput { a, b, fibonacci_number, out } =
+        input.parse::<F>().unwrap_or_else(|err| panic!("invalid input: {}", err));
 
     let mut a = ctx.load_witness(a);
     let mut b = ctx.load_witness(b);
@@ -55,3 +105,49 @@ pub fn compute_fibonacci<F: ScalarField>(
     println!("out: {:?}", out);
     assert_eq!(*out.value(), *b.value());
 }
+
+#[cfg(test)]
+mod tests {
+    use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+
+    fn input(a: &str, b: &str, fibonacci_number: usize, out: &str) -> CircuitInput {
+        CircuitInput { a: a.into(), b: b.into(), fibonacci_number, out: out.into() }
+    }
+
+    #[test]
+    fn test_parse() {
+        let parsed = input("1", "1", 10, "55").parse::<Fr>().unwrap();
+        assert_eq!(parsed.a, Fr::from(1));
+        assert_eq!(parsed.b, Fr::from(1));
+        assert_eq!(parsed.fibonacci_number, 10);
+        assert_eq!(parsed.out, Fr::from(55));
+    }
+
+    #[test]
+    fn test_parse_too_few_terms() {
+        for fibonacci_number in [0, 1] {
+            let err = input("1", "1", fibonacci_number, "1").parse::<Fr>().unwrap_err();
+            assert_eq!(err, InputError::TooFewTerms(fibonacci_number));
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_field_element() {
+        let cases = [
+            (input("one", "1", 10, "55"), "a"),
+            (input("1", "", 10, "55"), "b"),
+            (input("1", "1", 10, "5 5"), "out"),
+        ];
+        for (input, name) in cases {
+            match input.parse::<Fr>().unwrap_err() {
+                InputError::InvalidFieldElement { name: field, .. } => assert_eq!(field, name),
+                err => panic!("unexpected error: {}", err),
+            }
+        }
+
+        let err = input("1", "1", 10, "x").parse::<Fr>().unwrap_err();
+        assert_eq!(err, InputError::InvalidFieldElement { name: "out", value: "x".into() });
+    }
+}
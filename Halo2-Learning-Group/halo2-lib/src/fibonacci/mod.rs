@@ -1 +1,3 @@
+#[allow(clippy::module_inception)]
 pub mod fibonacci;
+pub mod variable_fibonacci;
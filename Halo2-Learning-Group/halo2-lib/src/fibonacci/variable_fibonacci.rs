@@ -0,0 +1,62 @@
+use halo2_base::gates::{GateChip, GateInstructions};
+use halo2_base::utils::ScalarField;
+use halo2_base::AssignedValue;
+use halo2_base::{Context, QuantumCell::Constant};
+use serde::{Deserialize, Serialize};
+
+// the circuit always computes this many terms, whatever the private index is
+pub const MAX_FIBONACCI_NUMBER: usize = 64;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CircuitInput {
+    pub a: String,
+    pub b: String,
+    // private, 1-indexed position of `out` in the sequence
+    pub n: String,
+    pub out: String,
+}
+
+// this algorithm takes the public first two terms, computes MAX_FIBONACCI_NUMBER terms,
+// selects the n-th one for a private n <= MAX_FIBONACCI_NUMBER, and outputs it as public output
+pub fn compute_variable_fibonacci<F: ScalarField>(
+    ctx: &mut Context<F>,
+    input: CircuitInput,
+    make_public: &mut Vec<AssignedValue<F>>,
+) {
+    let a = F::from_str_vartime(&input.a).expect("deserialize field element should not fail");
+    let b = F::from_str_vartime(&input.b).expect("deserialize field element should not fail");
+    let n = F::from_str_vartime(&input.n).expect("deserialize field element should not fail");
+    let out = F::from_str_vartime(&input.out).expect("deserialize field element should not fail");
+
+    let mut a = ctx.load_witness(a);
+    let mut b = ctx.load_witness(b);
+
+    make_public.push(a);
+    make_public.push(b);
+
+    let gate = GateChip::<F>::default();
+
+    let mut terms = vec![a, b];
+    for _row in 2..MAX_FIBONACCI_NUMBER {
+        let c = gate.add(ctx, a, b);
+        terms.push(c);
+        a = b;
+        b = c;
+    }
+
+    // terms[i] holds the (i + 1)-th Fibonacci number
+    let n = ctx.load_witness(n);
+    let idx = gate.sub(ctx, n, Constant(F::from(1)));
+    let indicator = gate.idx_to_indicator(ctx, idx, MAX_FIBONACCI_NUMBER);
+
+    // the indicator is all zeros when idx is out of range, which would select 0
+    let in_range = gate.sum(ctx, indicator.clone());
+    gate.assert_is_const(ctx, &in_range, &F::from(1));
+
+    let selected = gate.select_by_indicator(ctx, terms, indicator);
+
+    let out = ctx.load_witness(out);
+    make_public.push(out);
+
+    ctx.constrain_equal(&out, &selected);
+}
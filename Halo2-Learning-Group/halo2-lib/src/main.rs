@@ -4,6 +4,7 @@ use halo2_scaffold::scaffold::run;
 
 mod fibonacci;
 use fibonacci::fibonacci::compute_fibonacci;
+use fibonacci::variable_fibonacci::compute_variable_fibonacci;
 
 fn main() {
     env_logger::init();
@@ -11,5 +12,8 @@ fn main() {
     let args = Cli::parse();
 
     // run different zk commands based on the command line arguments
-    run(compute_fibonacci, args);
+    match args.name.clone().as_str() {
+        "variable_fibonacci" => run(compute_variable_fibonacci, args),
+        _ => run(compute_fibonacci, args),
+    }
 }
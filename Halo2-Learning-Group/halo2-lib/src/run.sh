@@ -2,4 +2,6 @@ cd ../
 
 pwd
 
-FIBONACCI_NUMBER=10 cargo run -- --name fibonacci -k 5 mock # for example, DEGREE=8
+cargo run -- --name fibonacci -k 5 mock # the length is read from data/fibonacci.in
+
+cargo run -- --name variable_fibonacci -k 8 mock # n is read from data/variable_fibonacci.in
@@ -0,0 +1,66 @@
+//! Group signatures as the group grows, from 16 to 4096 members.
+//!
+//! `GroupSignCircuit` takes a row per public key and the keys as public inputs,
+//! `MerkleGroupSignCircuit` a Poseidon hash per level of a tree of depth log2(n) and only
+//! the root. The rows used are printed once per size, proving time is measured by
+//! criterion.
+//!
+//! ```text
+//! cargo bench --bench group_sign
+//! ```
+
+mod common;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use halo2_examples::{
+    group_sign::{GroupSignCircuit, MerkleGroupSignCircuit},
+    merkle::{MerkleTree, PoseidonHasher},
+    proving,
+};
+use halo2_proofs::pasta::Fp;
+
+fn bench_group_sign(c: &mut Criterion) {
+    let mut group = c.benchmark_group("group sign");
+    group.sample_size(10);
+
+    let hasher = PoseidonHasher::new();
+    let message = [Fp::from(1234)];
+    // the signer is the last member, the secret key of member i being i + 1
+    let sk = |n: usize| Fp::from(n as u64);
+
+    for depth in [4, 8, 12] {
+        let n = 1 << depth;
+
+        let keys: Vec<Fp> = (1..=n as u64).map(|sk| hasher.hash(&[Fp::from(sk)])).collect();
+        let circuit = GroupSignCircuit::new(sk(n), n);
+
+        let (rows, k) = common::measure(&circuit, |config| config.constants, 0);
+        println!("{} members, running product: {} rows, k = {}", n, rows, k);
+
+        let (params, pk) = proving::setup(k, &circuit).unwrap();
+        group.bench_with_input(BenchmarkId::new("running product", n), &circuit, |b, circuit| {
+            b.iter(|| proving::prove(&params, &pk, circuit.clone(), &[&message, &keys]).unwrap())
+        });
+
+        let mut tree = MerkleTree::new(depth);
+        for (index, key) in keys.iter().enumerate() {
+            tree.insert(index as u64, *key);
+        }
+        let path = tree.path(n as u64 - 1);
+        let root = [tree.root()];
+        let circuit = MerkleGroupSignCircuit::new(sk(n), &path.siblings, &path.path_indices);
+
+        let (rows, k) = common::measure(&circuit, |config| config.constants, 0);
+        println!("{} members, merkle: {} rows, k = {}", n, rows, k);
+
+        let (params, pk) = proving::setup(k, &circuit).unwrap();
+        group.bench_with_input(BenchmarkId::new("merkle", n), &circuit, |b, circuit| {
+            b.iter(|| proving::prove(&params, &pk, circuit.clone(), &[&message, &root]).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_group_sign);
+criterion_main!(benches);
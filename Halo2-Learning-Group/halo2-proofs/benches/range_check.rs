@@ -0,0 +1,112 @@
+//! Batched range checks against one region per value, for 1k to 100k values.
+//!
+//! Each circuit witnesses its values in one region then range-checks them to 8 bits with
+//! `RangeCheckChip`, either with `range_check_batch` or with one `range_check` per value.
+//! The rows used are printed once per size, proving time is measured by criterion.
+//!
+//! ```text
+//! cargo bench --bench range_check
+//! ```
+
+mod common;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use halo2_examples::{
+    proving,
+    range_check::{RangeCheckChip, RangeCheckChipConfig, RangeCheckInstructions, TableRegistry},
+};
+use halo2_proofs::{
+    circuit::{floor_planner::V1, Layouter, Value},
+    pasta::Fp,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Fixed},
+};
+
+const NUM_BITS: usize = 8;
+
+#[derive(Clone)]
+struct RangeCheckCircuit {
+    values: Vec<Value<Fp>>,
+    batched: bool,
+}
+
+impl RangeCheckCircuit {
+    fn new(num_values: usize, batched: bool) -> Self {
+        let values = (0..num_values as u64).map(|v| Value::known(Fp::from(v % 256))).collect();
+        Self { values, batched }
+    }
+}
+
+impl Circuit<Fp> for RangeCheckCircuit {
+    type Config = (RangeCheckChipConfig<Fp, NUM_BITS, 256>, TableRegistry<Fp>, Column<Fixed>);
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self { values: vec![Value::unknown(); self.values.len()], batched: self.batched }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+
+        let value = meta.advice_column();
+        let mut tables = TableRegistry::new();
+        (RangeCheckChip::configure(meta, value, &mut tables), tables, constants)
+    }
+
+    fn synthesize(
+        &self,
+        (config, tables, _): Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        tables.load(&mut layouter)?;
+        let chip = RangeCheckChip::construct(config.clone());
+
+        let values = layouter.assign_region(
+            || "witness values",
+            |mut region| {
+                self.values
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, v)| {
+                        region.assign_advice(|| "value", config.value, offset, || *v)
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+
+        if self.batched {
+            chip.range_check_batch(layouter.namespace(|| "batch"), &values, NUM_BITS)
+        } else {
+            for (i, value) in values.iter().enumerate() {
+                chip.range_check(layouter.namespace(|| format!("value {}", i)), value, NUM_BITS)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn bench_range_check(c: &mut Criterion) {
+    let mut group = c.benchmark_group("range check 8 bits");
+    group.sample_size(10);
+
+    for num_values in [1_000, 10_000, 100_000] {
+        for batched in [true, false] {
+            let circuit = RangeCheckCircuit::new(num_values, batched);
+            let layout = if batched { "batched" } else { "one region per value" };
+
+            let (rows, k) = common::measure(&circuit, |config| config.2, 1 << NUM_BITS);
+            println!("{} values, {}: {} rows, k = {}", num_values, layout, rows, k);
+
+            let (params, pk) = proving::setup(k, &circuit).unwrap();
+
+            group.bench_with_input(BenchmarkId::new(layout, num_values), &circuit, |b, circuit| {
+                b.iter(|| proving::prove(&params, &pk, circuit.clone(), &[]).unwrap())
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_range_check);
+criterion_main!(benches);
@@ -0,0 +1,427 @@
+//! Test helpers checking that every witness of a circuit is constrained.
+//!
+//! The circuit is synthesized once to record its advice assignments, then once per recorded
+//! cell with that single value shifted by 2^200. Each mutated run must fail `MockProver::verify`,
+//! otherwise the cell is reported as under-constrained.
+//!
+//! The mutation happens below the floor planner, by wrapping the `Assignment` the prover hands
+//! to it, so any circuit can be checked without modifying its chips.
+
+use std::{cell::RefCell, fmt, marker::PhantomData};
+
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    circuit::{Layouter, Value},
+    dev::MockProver,
+    plonk::{
+        Advice, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, Fixed,
+        FloorPlanner, Instance, Selector,
+    },
+};
+
+/// An advice cell whose value can be changed without failing verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UnconstrainedCell {
+    pub(crate) region: String,
+    pub(crate) annotation: String,
+    pub(crate) column: Column<Advice>,
+    /// Offset of the cell within its region
+    pub(crate) offset: usize,
+}
+
+impl fmt::Display for UnconstrainedCell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` in region `{}` at offset {} of {:?}",
+            self.annotation, self.region, self.offset, self.column
+        )
+    }
+}
+
+/// Returns the advice cells of `circuit` that are not pinned down by its constraints.
+///
+/// Only one cell changes per run, the cells computed from it keeping their honest values. A
+/// cell that is free as long as the cells after it are recomputed from it, such as a term of
+/// a sequence whose own gate is disabled but which feeds the next gates, is not reported:
+/// chips laying out such chains should also test a prover forging a term and propagating it.
+///
+/// Panics if the unmodified circuit does not verify with the given public inputs.
+pub(crate) fn find_unconstrained_cells<F: FieldExt, C: Circuit<F>>(
+    k: u32,
+    circuit: C,
+    instance: Vec<Vec<F>>,
+) -> Vec<UnconstrainedCell> {
+    let circuit = Mutated(circuit);
+
+    STATE.with(|state| *state.borrow_mut() = MutationState::default());
+    let prover = MockProver::run(k, &circuit, instance.clone()).unwrap();
+    prover.assert_satisfied();
+
+    let (regions, cells) = STATE.with(|state| {
+        let state = state.take();
+        (state.regions, state.cells)
+    });
+
+    let mut unconstrained = vec![];
+    for (target, cell) in cells.into_iter().enumerate() {
+        STATE.with(|state| {
+            *state.borrow_mut() = MutationState { target: Some(target), ..Default::default() }
+        });
+
+        // a synthesis error counts as a rejection too
+        let rejected = match MockProver::run(k, &circuit, instance.clone()) {
+            Ok(prover) => prover.verify().is_err(),
+            Err(_) => true,
+        };
+
+        if !rejected {
+            let (region, offset) = match cell.region {
+                Some(index) => (regions[index].name.clone(), cell.row - regions[index].start),
+                None => ("<outside of any region>".to_string(), cell.row),
+            };
+            unconstrained.push(UnconstrainedCell {
+                region,
+                annotation: cell.annotation,
+                column: cell.column,
+                offset,
+            });
+        }
+    }
+
+    STATE.with(|state| state.take());
+
+    unconstrained
+}
+
+/// Panics, listing the offending cells, if any advice cell of `circuit` is under-constrained.
+pub(crate) fn assert_witness_sound<F: FieldExt, C: Circuit<F>>(
+    k: u32,
+    circuit: C,
+    instance: Vec<Vec<F>>,
+) {
+    let unconstrained = find_unconstrained_cells(k, circuit, instance);
+    if !unconstrained.is_empty() {
+        let cells: Vec<String> = unconstrained.iter().map(|cell| format!("  {}", cell)).collect();
+        panic!("under-constrained advice cells:\n{}", cells.join("\n"));
+    }
+}
+
+#[derive(Debug)]
+struct RegionRecord {
+    name: String,
+    //smallest row the region touched, which is where the floor planner placed it
+    start: usize,
+}
+
+#[derive(Debug)]
+struct CellRecord {
+    region: Option<usize>,
+    annotation: String,
+    column: Column<Advice>,
+    row: usize,
+}
+
+#[derive(Debug, Default)]
+struct MutationState {
+    // index of the advice assignment to perturb, None for the recording pass
+    target: Option<usize>,
+    count: usize,
+    current_region: Option<usize>,
+    regions: Vec<RegionRecord>,
+    cells: Vec<CellRecord>,
+}
+
+impl MutationState {
+    fn touch(&mut self, row: usize) {
+        if let Some(index) = self.current_region {
+            let region = &mut self.regions[index];
+            region.start = region.start.min(row);
+        }
+    }
+}
+
+thread_local! {
+    static STATE: RefCell<MutationState> = RefCell::new(MutationState::default());
+}
+
+/// Runs the wrapped circuit with `MutatingPlanner` on top of its own floor planner
+struct Mutated<C>(C);
+
+impl<F: FieldExt, C: Circuit<F>> Circuit<F> for Mutated<C> {
+    type Config = C::Config;
+    type FloorPlanner = MutatingPlanner<C::FloorPlanner>;
+
+    fn without_witnesses(&self) -> Self {
+        Mutated(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
+
+struct MutatingPlanner<P>(PhantomData<P>);
+
+impl<P: FloorPlanner> FloorPlanner for MutatingPlanner<P> {
+    fn synthesize<F: Field, CS: Assignment<F>, C: Circuit<F>>(
+        cs: &mut CS,
+        circuit: &C,
+        config: C::Config,
+        constants: Vec<Column<Fixed>>,
+    ) -> Result<(), Error> {
+        P::synthesize(
+            &mut MutatingAssignment { cs, _marker: PhantomData },
+            circuit,
+            config,
+            constants,
+        )
+    }
+}
+
+/// Forwards everything to the prover, recording the advice assignments and perturbing the
+/// targeted one
+struct MutatingAssignment<'a, F: Field, CS: Assignment<F>> {
+    cs: &'a mut CS,
+    _marker: PhantomData<F>,
+}
+
+impl<'a, F: Field, CS: Assignment<F>> Assignment<F> for MutatingAssignment<'a, F, CS> {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        let name: String = name_fn().into();
+        STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            state.current_region = Some(state.regions.len());
+            state.regions.push(RegionRecord { name: name.clone(), start: usize::MAX });
+        });
+        self.cs.enter_region(|| name)
+    }
+
+    fn exit_region(&mut self) {
+        STATE.with(|state| state.borrow_mut().current_region = None);
+        self.cs.exit_region()
+    }
+
+    fn enable_selector<A, AR>(
+        &mut self,
+        annotation: A,
+        selector: &Selector,
+        row: usize,
+    ) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        STATE.with(|state| state.borrow_mut().touch(row));
+        self.cs.enable_selector(annotation, selector, row)
+    }
+
+    fn query_instance(&self, column: Column<Instance>, row: usize) -> Result<Value<F>, Error> {
+        self.cs.query_instance(column, row)
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Advice>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let annotation: String = annotation().into();
+
+        let mutate = STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            state.touch(row);
+
+            let index = state.count;
+            state.count += 1;
+
+            match state.target {
+                Some(target) => target == index,
+                None => {
+                    let region = state.current_region;
+                    state.cells.push(CellRecord {
+                        region,
+                        annotation: annotation.clone(),
+                        column,
+                        row,
+                    });
+                    false
+                }
+            }
+        });
+
+        let value = to().map(|v| {
+            let v: Assigned<F> = v.into();
+            if mutate {
+                // far outside of the range of any small value the circuit could expect
+                v + Assigned::from(F::one().double().pow_vartime([200]))
+            } else {
+                v
+            }
+        });
+
+        self.cs.assign_advice(|| annotation, column, row, || value)
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        annotation: A,
+        column: Column<Fixed>,
+        row: usize,
+        to: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        STATE.with(|state| state.borrow_mut().touch(row));
+        self.cs.assign_fixed(annotation, column, row, to)
+    }
+
+    fn copy(
+        &mut self,
+        left_column: Column<halo2_proofs::plonk::Any>,
+        left_row: usize,
+        right_column: Column<halo2_proofs::plonk::Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        self.cs.copy(left_column, left_row, right_column, right_row)
+    }
+
+    fn fill_from_row(
+        &mut self,
+        column: Column<Fixed>,
+        row: usize,
+        to: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        self.cs.fill_from_row(column, row, to)
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.cs.push_namespace(name_fn)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.cs.pop_namespace(gadget_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::SimpleFloorPlanner, pasta::Fp, poly::Rotation};
+
+    use super::*;
+
+    /// a + b = c, with an optional extra witness that no gate looks at
+    #[derive(Default, Clone)]
+    struct MyCircuit {
+        a: Value<Fp>,
+        b: Value<Fp>,
+        dangling: bool,
+    }
+
+    impl Circuit<Fp> for MyCircuit {
+        type Config = (Column<Advice>, Column<Instance>, Selector);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { dangling: self.dangling, ..Default::default() }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = meta.advice_column();
+            let instance = meta.instance_column();
+            let selector = meta.selector();
+            meta.enable_equality(advice);
+            meta.enable_equality(instance);
+
+            meta.create_gate("add", |meta| {
+                let s = meta.query_selector(selector);
+                let a = meta.query_advice(advice, Rotation::cur());
+                let b = meta.query_advice(advice, Rotation::next());
+                let c = meta.query_advice(advice, Rotation(2));
+                vec![s * (a + b - c)]
+            });
+
+            (advice, instance, selector)
+        }
+
+        fn synthesize(
+            &self,
+            (advice, instance, selector): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let c = layouter.assign_region(
+                || "add",
+                |mut region| {
+                    selector.enable(&mut region, 0)?;
+                    region.assign_advice(|| "a", advice, 0, || self.a)?;
+                    region.assign_advice(|| "b", advice, 1, || self.b)?;
+                    region.assign_advice(|| "c", advice, 2, || self.a + self.b)
+                },
+            )?;
+
+            if self.dangling {
+                layouter.assign_region(
+                    || "dangling",
+                    |mut region| region.assign_advice(|| "unused", advice, 0, || self.a),
+                )?;
+            }
+
+            layouter.constrain_instance(c.cell(), instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_find_unconstrained_cells() {
+        let circuit = MyCircuit {
+            a: Value::known(Fp::from(2)),
+            b: Value::known(Fp::from(3)),
+            dangling: false,
+        };
+        let instance = vec![vec![Fp::from(5)]];
+
+        assert_witness_sound(4, circuit.clone(), instance.clone());
+
+        let unconstrained =
+            find_unconstrained_cells(4, MyCircuit { dangling: true, ..circuit }, instance);
+        assert_eq!(unconstrained.len(), 1);
+
+        // the region is laid out after the three rows of "add"
+        let cell = &unconstrained[0];
+        assert_eq!((cell.region.as_str(), cell.annotation.as_str()), ("dangling", "unused"));
+        assert_eq!(cell.offset, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "under-constrained advice cells")]
+    fn test_assert_witness_sound() {
+        let circuit = MyCircuit {
+            a: Value::known(Fp::from(2)),
+            b: Value::known(Fp::from(3)),
+            dangling: true,
+        };
+        assert_witness_sound(4, circuit, vec![vec![Fp::from(5)]]);
+    }
+}
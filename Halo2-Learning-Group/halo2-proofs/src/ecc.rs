@@ -0,0 +1,5 @@
+//! Arithmetic on a curve y² = x³ + b whose base field is the field of the circuit, such as
+//! Pallas points in a circuit over Fp and Vesta points in a circuit over Fq.
+pub mod chip;
+
+pub use chip::{EccChip, EccConfig, EccInstructions, EccPoint};
@@ -0,0 +1,796 @@
+use ff::PrimeFieldBits;
+use halo2_proofs::{
+    arithmetic::{Coordinates, CurveAffine, Field, FieldExt},
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use crate::range_check::{BitsChip, BitsConfig, BitsInstructions};
+
+/// Point arithmetic as the `double_and_add.py` example of lecture 6, in a circuit over the
+/// base field of `C`.
+pub trait EccInstructions<C: CurveAffine> {
+    /// Witnesses a point, constrained to be on the curve or the identity.
+    fn witness_point(
+        &self,
+        layouter: impl Layouter<C::Base>,
+        point: Value<C>,
+    ) -> Result<EccPoint<C>, Error>;
+
+    /// P + Q, for P and Q other than the identity with x_P ≠ x_Q, which the caller must
+    /// ensure: the constraints do not hold R down otherwise.
+    fn add_incomplete(
+        &self,
+        layouter: impl Layouter<C::Base>,
+        p: &EccPoint<C>,
+        q: &EccPoint<C>,
+    ) -> Result<EccPoint<C>, Error>;
+
+    /// P + Q for any points.
+    fn add(
+        &self,
+        layouter: impl Layouter<C::Base>,
+        p: &EccPoint<C>,
+        q: &EccPoint<C>,
+    ) -> Result<EccPoint<C>, Error>;
+
+    /// 2P for any point.
+    fn double(
+        &self,
+        layouter: impl Layouter<C::Base>,
+        p: &EccPoint<C>,
+    ) -> Result<EccPoint<C>, Error>;
+
+    /// [n]P, for the integer n < 2^num_bits held in `scalar`, by double-and-add from the most
+    /// significant bit.
+    fn mul(
+        &self,
+        layouter: impl Layouter<C::Base>,
+        scalar: &AssignedCell<C::Base, C::Base>,
+        num_bits: usize,
+        base: &EccPoint<C>,
+    ) -> Result<EccPoint<C>, Error>;
+}
+
+/// An affine point, the identity being (0, 0).
+///
+/// Pallas and Vesta have no point with x = 0, b = 5 not being a square in either field.
+#[derive(Debug, Clone)]
+pub struct EccPoint<C: CurveAffine> {
+    pub x: AssignedCell<C::Base, C::Base>,
+    pub y: AssignedCell<C::Base, C::Base>,
+}
+
+impl<C: CurveAffine> EccPoint<C> {
+    fn coordinates(&self) -> Value<(C::Base, C::Base)> {
+        self.x.value().copied().zip(self.y.value().copied())
+    }
+}
+
+/// The coordinates of `point`, (0, 0) for the identity
+fn coordinates<C: CurveAffine>(point: &C) -> (C::Base, C::Base) {
+    Option::from(point.coordinates())
+        .map(|c: Coordinates<C>| (*c.x(), *c.y()))
+        .unwrap_or((C::Base::zero(), C::Base::zero()))
+}
+
+/// 1/x, or 0 when x = 0
+fn inv0<F: Field>(x: F) -> F {
+    x.invert().unwrap_or(F::zero())
+}
+
+/// P + Q in affine coordinates, as `add` and `double` in `double_and_add.py`.
+fn add_native<F: FieldExt>((x_p, y_p): (F, F), (x_q, y_q): (F, F)) -> (F, F) {
+    if x_p.is_zero_vartime() && y_p.is_zero_vartime() {
+        return (x_q, y_q);
+    }
+    if x_q.is_zero_vartime() && y_q.is_zero_vartime() {
+        return (x_p, y_p);
+    }
+
+    let lambda = if x_p != x_q {
+        (y_q - y_p) * inv0(x_q - x_p)
+    } else if y_p == -y_q {
+        // P = -Q
+        return (F::zero(), F::zero());
+    } else {
+        x_p.square() * F::from(3) * inv0(y_p.double())
+    };
+
+    let x_r = lambda.square() - x_p - x_q;
+    let y_r = lambda * (x_p - x_r) - y_p;
+    (x_r, y_r)
+}
+
+/// The witnessed points, the sums and the selections between them share nine advice
+/// columns.
+///
+/// A witnessed point is on the curve or the identity:
+///
+/// ```text
+/// | a_0 | a_1 | q_point |
+/// -----------------------
+/// |  x  |  y  |    1    |        x (y² - x³ - b) = 0,  y (y² - x³ - b) = 0
+/// ```
+///
+/// The incomplete addition only checks R is on the line through P and Q:
+///
+/// ```text
+/// | a_0 | a_1 | a_2 | a_3 | q_add_incomplete |
+/// --------------------------------------------
+/// | x_p | y_p | x_q | y_q |        1         |
+/// |     |     | x_r | y_r |        0         |
+///
+/// (x_r + x_q + x_p)(x_p - x_q)² = (y_p - y_q)²
+/// (y_r + y_q)(x_p - x_q)        = (y_p - y_q)(x_q - x_r)
+/// ```
+///
+/// The complete addition is the one of the Orchard circuit, where α, β, γ and δ are inverses
+/// telling apart x_p = x_q, P = O, Q = O and P = -Q, and λ is the slope of the chord or of the
+/// tangent:
+///
+/// ```text
+/// | a_0 | a_1 | a_2 | a_3 | a_4 | a_5 | a_6 | a_7 | a_8 | q_add |
+/// ---------------------------------------------------------------
+/// | x_p | y_p | x_q | y_q |  λ  |  α  |  β  |  γ  |  δ  |   1   |
+/// |     |     | x_r | y_r |     |     |     |     |     |   0   |
+/// ```
+///
+/// A selection copies one of two points along a bit, which `BitsChip` constrains to be
+/// boolean:
+///
+/// ```text
+/// | a_0 | a_1 | a_2 | a_3 | a_4 | q_select |
+/// ------------------------------------------
+/// | x_a | y_a | x_b | y_b | bit |    1     |        out = a + bit (b - a)
+/// | x_o | y_o |     |     |     |    0     |
+/// ```
+#[derive(Debug, Clone)]
+pub struct EccConfig<C: CurveAffine> {
+    pub advices: [Column<Advice>; 9],
+    q_point: Selector,
+    q_add_incomplete: Selector,
+    q_add: Selector,
+    q_select: Selector,
+    bits: BitsConfig<C::Base>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EccChip<C: CurveAffine> {
+    config: EccConfig<C>,
+}
+
+impl<C: CurveAffine> EccChip<C>
+where
+    C::Base: PrimeFieldBits,
+{
+    pub fn construct(config: EccConfig<C>) -> Self {
+        Self { config }
+    }
+
+    /// Only supports curves with a = 0. A constants column must be enabled for the identity
+    /// the scalar multiplication starts from.
+    pub fn configure(
+        meta: &mut ConstraintSystem<C::Base>,
+        advices: [Column<Advice>; 9],
+    ) -> EccConfig<C> {
+        assert!(C::a().is_zero_vartime(), "a must be 0");
+
+        for column in &advices[..5] {
+            meta.enable_equality(*column);
+        }
+
+        let q_point = meta.selector();
+        let q_add_incomplete = meta.selector();
+        let q_add = meta.selector();
+        let q_select = meta.selector();
+
+        let bits = BitsChip::configure(meta, advices[4], advices[5]);
+
+        meta.create_gate("witness point", |meta| {
+            let q_point = meta.query_selector(q_point);
+            let x = meta.query_advice(advices[0], Rotation::cur());
+            let y = meta.query_advice(advices[1], Rotation::cur());
+
+            let b = Expression::Constant(C::b());
+            let curve = y.clone().square() - x.clone().square() * x.clone() - b;
+
+            Constraints::with_selector(
+                q_point,
+                [
+                    ("x on curve or identity", x * curve.clone()),
+                    ("y on curve or identity", y * curve),
+                ],
+            )
+        });
+
+        meta.create_gate("incomplete addition", |meta| {
+            let q_add_incomplete = meta.query_selector(q_add_incomplete);
+            let x_p = meta.query_advice(advices[0], Rotation::cur());
+            let y_p = meta.query_advice(advices[1], Rotation::cur());
+            let x_q = meta.query_advice(advices[2], Rotation::cur());
+            let y_q = meta.query_advice(advices[3], Rotation::cur());
+            let x_r = meta.query_advice(advices[2], Rotation::next());
+            let y_r = meta.query_advice(advices[3], Rotation::next());
+
+            let dx = x_p.clone() - x_q.clone();
+            let dy = y_p - y_q.clone();
+
+            Constraints::with_selector(
+                q_add_incomplete,
+                [
+                    (
+                        "x_r",
+                        (x_r.clone() + x_q.clone() + x_p) * dx.clone().square()
+                            - dy.clone().square(),
+                    ),
+                    ("y_r", (y_r + y_q) * dx - dy * (x_q - x_r)),
+                ],
+            )
+        });
+
+        meta.create_gate("complete addition", |meta| {
+            let q_add = meta.query_selector(q_add);
+            let x_p = meta.query_advice(advices[0], Rotation::cur());
+            let y_p = meta.query_advice(advices[1], Rotation::cur());
+            let x_q = meta.query_advice(advices[2], Rotation::cur());
+            let y_q = meta.query_advice(advices[3], Rotation::cur());
+            let lambda = meta.query_advice(advices[4], Rotation::cur());
+            let alpha = meta.query_advice(advices[5], Rotation::cur());
+            let beta = meta.query_advice(advices[6], Rotation::cur());
+            let gamma = meta.query_advice(advices[7], Rotation::cur());
+            let delta = meta.query_advice(advices[8], Rotation::cur());
+            let x_r = meta.query_advice(advices[2], Rotation::next());
+            let y_r = meta.query_advice(advices[3], Rotation::next());
+
+            let one = Expression::Constant(C::Base::one());
+            let three = Expression::Constant(C::Base::from(3));
+            let two = Expression::Constant(C::Base::from(2));
+
+            let dx = x_q.clone() - x_p.clone();
+            let sum_y = y_q.clone() + y_p.clone();
+            // 0 when P or Q is the identity
+            let x_pq = x_p.clone() * x_q.clone();
+
+            // R = P + Q when λ is the slope
+            let x_r_sum = lambda.clone().square() - x_p.clone() - x_q.clone() - x_r.clone();
+            let y_r_sum = lambda.clone() * (x_p.clone() - x_r.clone()) - y_p.clone() - y_r.clone();
+
+            // 1 if P = O, Q = O and P = -Q respectively, 0 otherwise
+            let p_is_identity = one.clone() - x_p.clone() * beta;
+            let q_is_identity = one.clone() - x_q.clone() * gamma;
+            let opposite = one.clone() - dx.clone() * alpha.clone() - sum_y.clone() * delta;
+
+            Constraints::with_selector(
+                q_add,
+                [
+                    (
+                        "chord slope",
+                        dx.clone() * (dx.clone() * lambda.clone() - (y_q.clone() - y_p.clone())),
+                    ),
+                    (
+                        "tangent slope",
+                        (one - dx.clone() * alpha)
+                            * (two * y_p.clone() * lambda - three * x_p.clone().square()),
+                    ),
+                    ("x_r, x_p ≠ x_q", x_pq.clone() * dx.clone() * x_r_sum.clone()),
+                    ("y_r, x_p ≠ x_q", x_pq.clone() * dx * y_r_sum.clone()),
+                    ("x_r, y_p ≠ -y_q", x_pq.clone() * sum_y.clone() * x_r_sum),
+                    ("y_r, y_p ≠ -y_q", x_pq * sum_y * y_r_sum),
+                    ("x_r, P = O", p_is_identity.clone() * (x_r.clone() - x_q.clone())),
+                    ("y_r, P = O", p_is_identity * (y_r.clone() - y_q)),
+                    ("x_r, Q = O", q_is_identity.clone() * (x_r.clone() - x_p)),
+                    ("y_r, Q = O", q_is_identity * (y_r.clone() - y_p)),
+                    ("x_r, P = -Q", opposite.clone() * x_r),
+                    ("y_r, P = -Q", opposite * y_r),
+                ],
+            )
+        });
+
+        meta.create_gate("select", |meta| {
+            let q_select = meta.query_selector(q_select);
+            let x_a = meta.query_advice(advices[0], Rotation::cur());
+            let y_a = meta.query_advice(advices[1], Rotation::cur());
+            let x_b = meta.query_advice(advices[2], Rotation::cur());
+            let y_b = meta.query_advice(advices[3], Rotation::cur());
+            let bit = meta.query_advice(advices[4], Rotation::cur());
+            let x_o = meta.query_advice(advices[0], Rotation::next());
+            let y_o = meta.query_advice(advices[1], Rotation::next());
+
+            Constraints::with_selector(
+                q_select,
+                [
+                    ("x", x_o - (x_a.clone() + bit.clone() * (x_b - x_a))),
+                    ("y", y_o - (y_a.clone() + bit * (y_b - y_a))),
+                ],
+            )
+        });
+
+        EccConfig { advices, q_point, q_add_incomplete, q_add, q_select, bits }
+    }
+
+    /// Lays out a point from its coordinates, which need not be on the curve for the tests.
+    fn assign_point(
+        &self,
+        mut layouter: impl Layouter<C::Base>,
+        point: Value<(C::Base, C::Base)>,
+    ) -> Result<EccPoint<C>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(
+            || "witness point",
+            |mut region| {
+                config.q_point.enable(&mut region, 0)?;
+
+                let x =
+                    region.assign_advice(|| "x", config.advices[0], 0, || point.map(|p| p.0))?;
+                let y =
+                    region.assign_advice(|| "y", config.advices[1], 0, || point.map(|p| p.1))?;
+                Ok(EccPoint { x, y })
+            },
+        )
+    }
+
+    /// The identity, fixed to (0, 0) with the constants column.
+    fn identity(&self, mut layouter: impl Layouter<C::Base>) -> Result<EccPoint<C>, Error> {
+        let advices = self.config.advices;
+
+        layouter.assign_region(
+            || "identity",
+            |mut region| {
+                let x =
+                    region.assign_advice_from_constant(|| "x", advices[0], 0, C::Base::zero())?;
+                let y =
+                    region.assign_advice_from_constant(|| "y", advices[1], 0, C::Base::zero())?;
+                Ok(EccPoint { x, y })
+            },
+        )
+    }
+
+    /// Lays out P, Q and R = P + Q, with the helper cells of the complete addition if
+    /// `complete`.
+    fn assign_add(
+        &self,
+        mut layouter: impl Layouter<C::Base>,
+        p: &EccPoint<C>,
+        q: &EccPoint<C>,
+        complete: bool,
+    ) -> Result<EccPoint<C>, Error> {
+        let config = &self.config;
+        let advices = config.advices;
+
+        let (p_xy, q_xy) = (p.coordinates(), q.coordinates());
+        let r = p_xy.zip(q_xy).map(|(p, q)| add_native(p, q));
+
+        layouter.assign_region(
+            || if complete { "complete addition" } else { "incomplete addition" },
+            |mut region| {
+                if complete {
+                    config.q_add.enable(&mut region, 0)?;
+                } else {
+                    config.q_add_incomplete.enable(&mut region, 0)?;
+                }
+
+                p.x.copy_advice(|| "x_p", &mut region, advices[0], 0)?;
+                p.y.copy_advice(|| "y_p", &mut region, advices[1], 0)?;
+                q.x.copy_advice(|| "x_q", &mut region, advices[2], 0)?;
+                q.y.copy_advice(|| "y_q", &mut region, advices[3], 0)?;
+
+                if complete {
+                    let helpers = p_xy.zip(q_xy).map(|((x_p, y_p), (x_q, y_q))| {
+                        let lambda = if x_p != x_q {
+                            (y_q - y_p) * inv0(x_q - x_p)
+                        } else if !y_p.is_zero_vartime() {
+                            x_p.square() * C::Base::from(3) * inv0(y_p.double())
+                        } else {
+                            C::Base::zero()
+                        };
+                        let delta = if x_p == x_q { inv0(y_q + y_p) } else { C::Base::zero() };
+
+                        [lambda, inv0(x_q - x_p), inv0(x_p), inv0(x_q), delta]
+                    });
+
+                    let helpers = helpers.transpose_array();
+                    for (i, (name, value)) in
+                        ["λ", "α", "β", "γ", "δ"].iter().zip(helpers).enumerate()
+                    {
+                        region.assign_advice(|| *name, advices[4 + i], 0, || value)?;
+                    }
+                }
+
+                let x = region.assign_advice(|| "x_r", advices[2], 1, || r.map(|r| r.0))?;
+                let y = region.assign_advice(|| "y_r", advices[3], 1, || r.map(|r| r.1))?;
+                Ok(EccPoint { x, y })
+            },
+        )
+    }
+
+    /// b if `bit` is 1, a if it is 0
+    fn select(
+        &self,
+        mut layouter: impl Layouter<C::Base>,
+        bit: &AssignedCell<C::Base, C::Base>,
+        a: &EccPoint<C>,
+        b: &EccPoint<C>,
+    ) -> Result<EccPoint<C>, Error> {
+        let config = &self.config;
+        let advices = config.advices;
+
+        layouter.assign_region(
+            || "select",
+            |mut region| {
+                config.q_select.enable(&mut region, 0)?;
+
+                a.x.copy_advice(|| "x_a", &mut region, advices[0], 0)?;
+                a.y.copy_advice(|| "y_a", &mut region, advices[1], 0)?;
+                b.x.copy_advice(|| "x_b", &mut region, advices[2], 0)?;
+                b.y.copy_advice(|| "y_b", &mut region, advices[3], 0)?;
+                bit.copy_advice(|| "bit", &mut region, advices[4], 0)?;
+
+                let out = bit
+                    .value()
+                    .zip(a.coordinates())
+                    .zip(b.coordinates())
+                    .map(|((bit, a), b)| if bit.is_zero_vartime() { a } else { b });
+
+                let x = region.assign_advice(|| "x_o", advices[0], 1, || out.map(|o| o.0))?;
+                let y = region.assign_advice(|| "y_o", advices[1], 1, || out.map(|o| o.1))?;
+                Ok(EccPoint { x, y })
+            },
+        )
+    }
+}
+
+impl<C: CurveAffine> EccInstructions<C> for EccChip<C>
+where
+    C::Base: PrimeFieldBits,
+{
+    fn witness_point(
+        &self,
+        layouter: impl Layouter<C::Base>,
+        point: Value<C>,
+    ) -> Result<EccPoint<C>, Error> {
+        self.assign_point(layouter, point.map(|p| coordinates(&p)))
+    }
+
+    fn add_incomplete(
+        &self,
+        layouter: impl Layouter<C::Base>,
+        p: &EccPoint<C>,
+        q: &EccPoint<C>,
+    ) -> Result<EccPoint<C>, Error> {
+        p.x.value().zip(q.x.value()).error_if_known_and(|(x_p, x_q)| x_p == x_q)?;
+        self.assign_add(layouter, p, q, false)
+    }
+
+    fn add(
+        &self,
+        layouter: impl Layouter<C::Base>,
+        p: &EccPoint<C>,
+        q: &EccPoint<C>,
+    ) -> Result<EccPoint<C>, Error> {
+        self.assign_add(layouter, p, q, true)
+    }
+
+    fn double(
+        &self,
+        layouter: impl Layouter<C::Base>,
+        p: &EccPoint<C>,
+    ) -> Result<EccPoint<C>, Error> {
+        self.assign_add(layouter, p, p, true)
+    }
+
+    /// Each bit takes a doubling, an addition and a selection, all complete as the running
+    /// point may be the identity, equal to the base or its opposite.
+    fn mul(
+        &self,
+        mut layouter: impl Layouter<C::Base>,
+        scalar: &AssignedCell<C::Base, C::Base>,
+        num_bits: usize,
+        base: &EccPoint<C>,
+    ) -> Result<EccPoint<C>, Error> {
+        let bits_chip = BitsChip::construct(self.config.bits.clone());
+        let bits = bits_chip.num_to_bits(layouter.namespace(|| "scalar bits"), scalar, num_bits)?;
+
+        let mut acc = self.identity(layouter.namespace(|| "O"))?;
+        for (i, bit) in bits.iter().enumerate().rev() {
+            // the first doubling is of the identity
+            if i + 1 < num_bits {
+                acc = self.double(layouter.namespace(|| format!("double {}", i)), &acc)?;
+            }
+            let sum = self.add(layouter.namespace(|| format!("add {}", i)), &acc, base)?;
+            acc = self.select(layouter.namespace(|| format!("select {}", i)), bit, &acc, &sum)?;
+        }
+
+        Ok(acc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::floor_planner::V1,
+        dev::MockProver,
+        pasta::{
+            group::{prime::PrimeCurveAffine, Curve},
+            pallas, vesta, Fp, Fq,
+        },
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::dev::find_unconstrained_cells;
+
+    /// Witnesses P and Q then exposes P + Q and 2P
+    #[derive(Clone)]
+    struct AddCircuit<C: CurveAffine> {
+        p: Value<(C::Base, C::Base)>,
+        q: Value<(C::Base, C::Base)>,
+        incomplete: bool,
+    }
+
+    impl<C: CurveAffine> AddCircuit<C> {
+        fn new(p: C, q: C, incomplete: bool) -> Self {
+            Self { p: Value::known(coordinates(&p)), q: Value::known(coordinates(&q)), incomplete }
+        }
+    }
+
+    impl<C: CurveAffine> Circuit<C::Base> for AddCircuit<C>
+    where
+        C::Base: PrimeFieldBits,
+    {
+        type Config = (EccConfig<C>, Column<Instance>);
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self { p: Value::unknown(), q: Value::unknown(), incomplete: self.incomplete }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<C::Base>) -> Self::Config {
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            let advices = [(); 9].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            (EccChip::configure(meta, advices), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<C::Base>,
+        ) -> Result<(), Error> {
+            let chip = EccChip::construct(config);
+
+            let p = chip.assign_point(layouter.namespace(|| "P"), self.p)?;
+            let q = chip.assign_point(layouter.namespace(|| "Q"), self.q)?;
+
+            let sum = if self.incomplete {
+                chip.add_incomplete(layouter.namespace(|| "P + Q"), &p, &q)?
+            } else {
+                chip.add(layouter.namespace(|| "P + Q"), &p, &q)?
+            };
+            let double = chip.double(layouter.namespace(|| "2P"), &p)?;
+
+            for (row, cell) in [sum.x, sum.y, double.x, double.y].iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Witnesses P and the scalar n then exposes [n]P
+    #[derive(Clone)]
+    struct MulCircuit<C: CurveAffine> {
+        base: Value<C>,
+        scalar: Value<C::Base>,
+        num_bits: usize,
+    }
+
+    impl<C: CurveAffine> Circuit<C::Base> for MulCircuit<C>
+    where
+        C::Base: PrimeFieldBits,
+    {
+        type Config = (EccConfig<C>, Column<Instance>);
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self { base: Value::unknown(), scalar: Value::unknown(), num_bits: self.num_bits }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<C::Base>) -> Self::Config {
+            AddCircuit::<C>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<C::Base>,
+        ) -> Result<(), Error> {
+            let advice = config.advices[0];
+            let chip = EccChip::construct(config);
+
+            let base = chip.witness_point(layouter.namespace(|| "P"), self.base)?;
+            let scalar = layouter.assign_region(
+                || "Witness n",
+                |mut region| region.assign_advice(|| "n", advice, 0, || self.scalar),
+            )?;
+
+            let product = chip.mul(layouter.namespace(|| "[n]P"), &scalar, self.num_bits, &base)?;
+
+            layouter.constrain_instance(product.x.cell(), instance, 0)?;
+            layouter.constrain_instance(product.y.cell(), instance, 1)
+        }
+    }
+
+    fn expose<C: CurveAffine>(points: &[C]) -> Vec<Vec<C::Base>> {
+        vec![points.iter().flat_map(|p| <[C::Base; 2]>::from(coordinates(p))).collect()]
+    }
+
+    fn check_add<C: CurveAffine>(p: C, q: C, incomplete: bool) -> Result<(), ()>
+    where
+        C::Base: PrimeFieldBits,
+    {
+        let sum = (p.to_curve() + q).to_affine();
+        let double = (p.to_curve() + p).to_affine();
+
+        let circuit = AddCircuit::new(p, q, incomplete);
+        let prover = MockProver::run(5, &circuit, expose(&[sum, double])).unwrap();
+        prover.verify().map_err(|_| ())
+    }
+
+    /// The integer n as a scalar
+    fn scalar_of<C: CurveAffine>(n: C::Base) -> C::Scalar
+    where
+        C::Base: PrimeFieldBits,
+    {
+        n.to_le_bits().iter().by_vals().rev().fold(C::Scalar::zero(), |acc, bit| {
+            acc.double() + if bit { C::Scalar::one() } else { C::Scalar::zero() }
+        })
+    }
+
+    /// Proves [n]P is the product by `claimed`
+    fn check_mul<C: CurveAffine>(
+        k: u32,
+        base: C,
+        n: C::Base,
+        num_bits: usize,
+        claimed: C::Base,
+    ) -> Result<(), ()>
+    where
+        C::Base: PrimeFieldBits,
+    {
+        let product = (base * scalar_of::<C>(claimed)).to_affine();
+
+        let circuit = MulCircuit { base: Value::known(base), scalar: Value::known(n), num_bits };
+        let prover = MockProver::run(k, &circuit, expose(&[product])).unwrap();
+        prover.verify().map_err(|_| ())
+    }
+
+    fn points<C: CurveAffine>() -> [C; 3] {
+        let g = C::generator();
+        [g, (g * C::Scalar::from(7)).to_affine(), (g * C::Scalar::from(1 << 40)).to_affine()]
+    }
+
+    #[test]
+    fn test_no_point_with_x_zero() {
+        assert!(bool::from(Fp::from(5).sqrt().is_none()));
+        assert!(bool::from(Fq::from(5).sqrt().is_none()));
+    }
+
+    #[test]
+    fn test_add() {
+        fn check<C: CurveAffine>()
+        where
+            C::Base: PrimeFieldBits,
+        {
+            let [g, p, q] = points::<C>();
+            let identity = C::identity();
+
+            for incomplete in [false, true] {
+                assert_eq!(check_add(p, q, incomplete), Ok(()));
+                assert_eq!(check_add(g, q, incomplete), Ok(()));
+            }
+
+            // the cases only the complete addition handles
+            for (p, q) in [(p, p), (p, -p), (p, identity), (identity, q), (identity, identity)] {
+                assert_eq!(check_add(p, q, false), Ok(()));
+            }
+        }
+
+        check::<pallas::Affine>();
+        check::<vesta::Affine>();
+    }
+
+    #[test]
+    fn test_add_malicious_prover() {
+        let [_, p, q] = points::<pallas::Affine>();
+        let sum = (p.to_curve() + q).to_affine();
+        let double = (p.to_curve() + p).to_affine();
+
+        // a wrong sum, for both additions
+        for incomplete in [false, true] {
+            let circuit = AddCircuit::new(p, q, incomplete);
+            let prover = MockProver::run(5, &circuit, expose(&[sum, sum])).unwrap();
+            assert!(prover.verify().is_err());
+        }
+
+        // a point off the curve
+        let (x, y) = coordinates(&p);
+        let circuit = AddCircuit::<pallas::Affine> {
+            p: Value::known((x, y + Fp::one())),
+            q: Value::known(coordinates(&q)),
+            incomplete: false,
+        };
+        let off_curve = (x, y + Fp::one());
+        let expected: Vec<Fp> =
+            [add_native(off_curve, coordinates(&q)), add_native(off_curve, off_curve)]
+                .iter()
+                .flat_map(|(x, y)| [*x, *y])
+                .collect();
+        let prover = MockProver::run(5, &circuit, vec![expected]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // the incomplete addition refuses P + P
+        let circuit = AddCircuit::new(p, p, true);
+        assert!(MockProver::run(5, &circuit, expose(&[double, double])).is_err());
+    }
+
+    #[test]
+    fn test_mul() {
+        // each bit takes 6 rows
+        let k = 11;
+
+        // a 254-bit scalar
+        let n = Fp::from_u128(u128::MAX) * Fp::from_u128(u128::MAX >> 2);
+        for base in points::<pallas::Affine>() {
+            assert_eq!(check_mul(k, base, n, 254, n), Ok(()));
+            assert!(check_mul(k, base, n, 254, n + Fp::one()).is_err());
+        }
+
+        let k = 8;
+        for base in points::<vesta::Affine>() {
+            for n in [0, 1, 2, 3, 0xdead_beef] {
+                assert_eq!(check_mul(k, base, Fq::from(n), 32, Fq::from(n)), Ok(()));
+            }
+        }
+
+        // the identity as the base
+        assert_eq!(check_mul(k, vesta::Affine::identity(), Fq::from(5), 32, Fq::from(5)), Ok(()));
+
+        // a scalar that does not fit in the bits
+        let (g, n) = (vesta::Affine::generator(), Fq::from(1 << 32));
+        assert!(check_mul(k, g, n, 32, n).is_err());
+        assert!(check_mul(k, g, n, 32, Fq::zero()).is_err());
+    }
+
+    #[test]
+    fn test_ecc_witness_sound() {
+        // only inverses and slopes multiplied by 0 may be free, such as in O + O
+        let helpers = ["λ", "α", "β", "γ", "δ"];
+
+        let [_, p, q] = points::<pallas::Affine>();
+        let sum = (p.to_curve() + q).to_affine();
+        let double = (p.to_curve() + p).to_affine();
+        for incomplete in [false, true] {
+            let circuit = AddCircuit::new(p, q, incomplete);
+            let free = find_unconstrained_cells(5, circuit, expose(&[sum, double]));
+            // α when doubling
+            for cell in free {
+                assert!(helpers.contains(&cell.annotation.as_str()), "{}", cell);
+            }
+        }
+
+        let base = pallas::Affine::generator();
+        let n = Fp::from(5);
+        let product = (base * pallas::Scalar::from(5)).to_affine();
+        let circuit = MulCircuit { base: Value::known(base), scalar: Value::known(n), num_bits: 3 };
+        let free = find_unconstrained_cells(7, circuit, expose(&[product]));
+        for cell in free {
+            assert!(helpers.contains(&cell.annotation.as_str()), "{}", cell);
+        }
+    }
+}
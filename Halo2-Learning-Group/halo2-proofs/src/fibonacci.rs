@@ -1 +1,7 @@
-mod fibonacci;
+pub mod chip;
+pub mod fast_fibonacci;
+#[allow(clippy::module_inception)]
+pub mod fibonacci;
+pub mod fibonacci_mod;
+pub mod linear_recurrence;
+pub mod variable_fibonacci;
@@ -0,0 +1,212 @@
+use std::{fmt::Debug, marker::PhantomData};
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{floor_planner::V1, *},
+    plonk::*,
+};
+
+use super::fibonacci::FibonacciError;
+
+pub mod single_column;
+pub mod three_column;
+
+pub use single_column::SingleColumnChip;
+pub use three_column::ThreeColumnChip;
+
+/// A Fibonacci term assigned by one of the chips
+#[derive(Debug, Clone)]
+pub struct Acell<F: FieldExt>(pub AssignedCell<F, F>);
+
+/// The cells of `a`, `b` and `c` on the first row
+pub type FirstRow<F> = (Acell<F>, Acell<F>, Acell<F>);
+
+/// Instructions shared by the Fibonacci layouts, each step lives in its own region and is
+/// linked to the previous one with copy constraints.
+pub trait FibonacciInstructions<F: FieldExt>: Sized {
+    type Config: Debug + Clone;
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config;
+
+    fn construct(config: Self::Config) -> Self;
+
+    /// Witnesses the two seeds `a` and `b` along with their sum.
+    fn assign_first_row(
+        &self,
+        layouter: impl Layouter<F>,
+        a: Value<F>,
+        b: Value<F>,
+    ) -> Result<FirstRow<F>, Error>;
+
+    /// Copies the two previous terms and assigns their sum.
+    fn assign_next(
+        &self,
+        layouter: impl Layouter<F>,
+        prev_b: &Acell<F>,
+        prev_c: &Acell<F>,
+    ) -> Result<Acell<F>, Error>;
+
+    fn expose_public(
+        &self,
+        layouter: impl Layouter<F>,
+        cell: &Acell<F>,
+        row: usize,
+    ) -> Result<(), Error>;
+}
+
+/// Circuit exposing the two private seeds and the `num_terms`-th term, laid out by the chip `C`.
+#[derive(Debug, Clone)]
+pub struct FiboCircuit<F: FieldExt, C> {
+    pub a: Value<F>,
+    pub b: Value<F>,
+    num_terms: usize,
+    _marker: PhantomData<C>,
+}
+
+impl<F: FieldExt, C> FiboCircuit<F, C> {
+    pub fn new(a: Value<F>, b: Value<F>, num_terms: usize) -> Result<Self, FibonacciError> {
+        //the first row already holds three terms
+        if num_terms < 3 {
+            return Err(FibonacciError::TooFewTerms(num_terms));
+        }
+
+        Ok(Self { a, b, num_terms, _marker: PhantomData })
+    }
+
+    pub fn num_terms(&self) -> usize {
+        self.num_terms
+    }
+}
+
+impl<F: FieldExt, C: FibonacciInstructions<F>> Circuit<F> for FiboCircuit<F, C> {
+    type Config = C::Config;
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            a: Value::unknown(),
+            b: Value::unknown(),
+            num_terms: self.num_terms,
+            _marker: PhantomData,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        C::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = C::construct(config);
+        let (prev_a, mut prev_b, mut prev_c) =
+            chip.assign_first_row(layouter.namespace(|| "first row"), self.a, self.b)?;
+
+        // Define the copy constraint from the instance column to our relevant advice cell
+        chip.expose_public(layouter.namespace(|| "private a"), &prev_a, 0)?;
+        chip.expose_public(layouter.namespace(|| "private b"), &prev_b, 1)?;
+
+        for _ in 3..self.num_terms {
+            let c_cell = chip.assign_next(layouter.namespace(|| "next row"), &prev_b, &prev_c)?;
+            prev_b = prev_c;
+            prev_c = c_cell;
+        }
+
+        chip.expose_public(layouter.namespace(|| "out"), &prev_c, 2)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    use super::*;
+    use crate::dev::assert_witness_sound;
+
+    fn fibonacci(a: Fp, b: Fp, num_terms: usize) -> Fp {
+        let (mut a, mut b) = (a, b);
+        for _ in 2..num_terms {
+            (a, b) = (b, a + b);
+        }
+        b
+    }
+
+    fn run<C: FibonacciInstructions<Fp>>(
+        a: Fp,
+        b: Fp,
+        num_terms: usize,
+        public_input: Vec<Fp>,
+    ) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+        let k = 6;
+        let circuit =
+            FiboCircuit::<Fp, C>::new(Value::known(a), Value::known(b), num_terms).unwrap();
+        MockProver::run(k, &circuit, vec![public_input]).unwrap().verify()
+    }
+
+    #[test]
+    fn test_fibo_chips() {
+        let (a, b) = (Fp::from(1), Fp::from(1));
+        let public_input = vec![a, b, Fp::from(55)];
+
+        assert!(run::<ThreeColumnChip<Fp>>(a, b, 10, public_input.clone()).is_ok());
+        assert!(run::<SingleColumnChip<Fp>>(a, b, 10, public_input).is_ok());
+    }
+
+    #[test]
+    fn test_fibo_chips_same_outputs() {
+        for (a, b) in [(1, 1), (2, 1), (3, 7)] {
+            let (a, b) = (Fp::from(a), Fp::from(b));
+
+            for num_terms in [3, 4, 10, 15] {
+                let out = fibonacci(a, b, num_terms);
+                let public_input = vec![a, b, out];
+
+                assert_eq!(
+                    run::<ThreeColumnChip<Fp>>(a, b, num_terms, public_input.clone()),
+                    Ok(())
+                );
+                assert_eq!(run::<SingleColumnChip<Fp>>(a, b, num_terms, public_input), Ok(()));
+
+                // and they reject the same wrong output
+                let wrong_input = vec![a, b, out + Fp::one()];
+                assert!(run::<ThreeColumnChip<Fp>>(a, b, num_terms, wrong_input.clone()).is_err());
+                assert!(run::<SingleColumnChip<Fp>>(a, b, num_terms, wrong_input).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_fibo_chips_distinct_seeds() {
+        // b used to be witnessed from a, which only went unnoticed with equal seeds
+        let (a, b) = (Fp::from(2), Fp::from(3));
+        let public_input = vec![a, b, fibonacci(a, b, 5)];
+
+        assert!(run::<ThreeColumnChip<Fp>>(a, b, 5, public_input.clone()).is_ok());
+        assert!(run::<SingleColumnChip<Fp>>(a, b, 5, public_input).is_ok());
+    }
+
+    #[test]
+    fn test_fibo_circuit_too_few_terms() {
+        let circuit =
+            FiboCircuit::<Fp, ThreeColumnChip<Fp>>::new(Value::unknown(), Value::unknown(), 2);
+        assert_eq!(circuit.unwrap_err(), FibonacciError::TooFewTerms(2));
+    }
+
+    #[test]
+    fn test_fibo_chips_witness_sound() {
+        let (a, b) = (Fp::from(2), Fp::from(3));
+        let public_input = vec![a, b, fibonacci(a, b, 10)];
+
+        let circuit =
+            FiboCircuit::<Fp, ThreeColumnChip<Fp>>::new(Value::known(a), Value::known(b), 10);
+        assert_witness_sound(6, circuit.unwrap(), vec![public_input.clone()]);
+
+        let circuit =
+            FiboCircuit::<Fp, SingleColumnChip<Fp>>::new(Value::known(a), Value::known(b), 10);
+        assert_witness_sound(6, circuit.unwrap(), vec![public_input]);
+    }
+}
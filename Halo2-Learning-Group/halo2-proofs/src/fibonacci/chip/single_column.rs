@@ -0,0 +1,117 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use super::{Acell, FibonacciInstructions, FirstRow};
+
+/// Every step takes three rows of the same column, the two previous terms being copied in:
+///
+/// ```text
+/// |  advice  |  s  |
+/// ------------------
+/// |    1     |  1  |
+/// |    1     |     |
+/// |    2     |     |
+/// |    1     |  1  |
+/// |    2     |     |
+/// |    3     |     |
+/// ```
+#[derive(Debug, Clone)]
+pub struct SingleColumnConfig {
+    pub advice: Column<Advice>,
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SingleColumnChip<F: FieldExt> {
+    config: SingleColumnConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FibonacciInstructions<F> for SingleColumnChip<F> {
+    type Config = SingleColumnConfig;
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> SingleColumnConfig {
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+
+        let selector = meta.selector();
+
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+
+        meta.create_gate("add", |meta: &mut VirtualCells<F>| {
+            // advice | selector
+            //   a |    s
+            //   b |
+            //   c |
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(advice, Rotation::cur()); //rotation is indexed to selector s position
+            let b = meta.query_advice(advice, Rotation::next());
+            let c = meta.query_advice(advice, Rotation(2));
+            vec![s * (a + b - c)]
+        });
+
+        SingleColumnConfig { advice, selector, instance }
+    }
+
+    fn construct(config: SingleColumnConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    fn assign_first_row(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Value<F>,
+        b: Value<F>,
+    ) -> Result<FirstRow<F>, Error> {
+        layouter.assign_region(
+            || "first row",
+            |mut region| {
+                self.config.selector.enable(&mut region, 0)?;
+
+                let a_cell =
+                    region.assign_advice(|| "a", self.config.advice, 0, || a).map(Acell)?;
+
+                let b_cell =
+                    region.assign_advice(|| "b", self.config.advice, 1, || b).map(Acell)?;
+
+                let c_cell =
+                    region.assign_advice(|| "c", self.config.advice, 2, || a + b).map(Acell)?;
+
+                Ok((a_cell, b_cell, c_cell))
+            },
+        )
+    }
+
+    fn assign_next(
+        &self,
+        mut layouter: impl Layouter<F>,
+        prev_b: &Acell<F>,
+        prev_c: &Acell<F>,
+    ) -> Result<Acell<F>, Error> {
+        layouter.assign_region(
+            || "next row",
+            |mut region: Region<F>| {
+                self.config.selector.enable(&mut region, 0)?;
+
+                prev_b.0.copy_advice(|| "a", &mut region, self.config.advice, 0)?;
+                prev_c.0.copy_advice(|| "b", &mut region, self.config.advice, 1)?;
+
+                let c_val = prev_b.0.value().and_then(|b| prev_c.0.value().map(|c| *b + *c));
+
+                region.assign_advice(|| "c", self.config.advice, 2, || c_val).map(Acell)
+            },
+        )
+    }
+
+    fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &Acell<F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.0.cell(), self.config.instance, row)
+    }
+}
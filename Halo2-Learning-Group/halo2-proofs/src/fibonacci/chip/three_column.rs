@@ -0,0 +1,117 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
+
+use super::{Acell, FibonacciInstructions, FirstRow};
+
+/// Every step takes one row:
+///
+/// ```text
+/// |  a  |  b  |  c  |  s  |
+/// -------------------------
+/// |  1  |  1  |  2  |  1  |
+/// |  1  |  2  |  3  |  1  |
+/// |  2  |  3  |  5  |  1  |
+/// ```
+#[derive(Debug, Clone)]
+pub struct ThreeColumnConfig {
+    pub advice: [Column<Advice>; 3],
+    pub selector: Selector,
+    pub instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ThreeColumnChip<F: FieldExt> {
+    config: ThreeColumnConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FibonacciInstructions<F> for ThreeColumnChip<F> {
+    type Config = ThreeColumnConfig;
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> ThreeColumnConfig {
+        let col_a = meta.advice_column();
+        let col_b = meta.advice_column();
+        let col_c = meta.advice_column();
+        let instance = meta.instance_column();
+
+        let selector = meta.selector();
+
+        meta.enable_equality(col_a);
+        meta.enable_equality(col_b);
+        meta.enable_equality(col_c);
+        meta.enable_equality(instance);
+
+        meta.create_gate("add", |meta: &mut VirtualCells<F>| {
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let b = meta.query_advice(col_b, Rotation::cur());
+            let c = meta.query_advice(col_c, Rotation::cur());
+            vec![s * (a + b - c)]
+        });
+
+        ThreeColumnConfig { advice: [col_a, col_b, col_c], selector, instance }
+    }
+
+    fn construct(config: ThreeColumnConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    fn assign_first_row(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: Value<F>,
+        b: Value<F>,
+    ) -> Result<FirstRow<F>, Error> {
+        layouter.assign_region(
+            || "first row",
+            |mut region| {
+                self.config.selector.enable(&mut region, 0)?;
+
+                let a_cell =
+                    region.assign_advice(|| "a", self.config.advice[0], 0, || a).map(Acell)?;
+
+                let b_cell =
+                    region.assign_advice(|| "b", self.config.advice[1], 0, || b).map(Acell)?;
+
+                let c_val = a + b;
+
+                let c_cell =
+                    region.assign_advice(|| "c", self.config.advice[2], 0, || c_val).map(Acell)?;
+
+                Ok((a_cell, b_cell, c_cell))
+            },
+        )
+    }
+
+    fn assign_next(
+        &self,
+        mut layouter: impl Layouter<F>,
+        prev_b: &Acell<F>,
+        prev_c: &Acell<F>,
+    ) -> Result<Acell<F>, Error> {
+        layouter.assign_region(
+            || "next row",
+            |mut region: Region<F>| {
+                self.config.selector.enable(&mut region, 0)?;
+
+                //permutation trick
+                prev_b.0.copy_advice(|| "a", &mut region, self.config.advice[0], 0)?;
+                prev_c.0.copy_advice(|| "b", &mut region, self.config.advice[1], 0)?;
+
+                let c_val = prev_b.0.value().and_then(|b| prev_c.0.value().map(|c| *b + *c));
+
+                region.assign_advice(|| "c", self.config.advice[2], 0, || c_val).map(Acell)
+            },
+        )
+    }
+
+    fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &Acell<F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.0.cell(), self.config.instance, row)
+    }
+}
@@ -0,0 +1,310 @@
+use ff::PrimeFieldBits;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{floor_planner::V1, *},
+    plonk::*,
+    poly::Rotation,
+};
+
+use crate::range_check::{decompose_range_check::DecomposeConfig, table::TableRegistry};
+
+/// Computes F(n) in O(log n) rows with the fast-doubling identities
+///
+/// ```text
+/// F(2k)     = F(k) * (2 * F(k+1) - F(k))
+/// F(2k + 1) = F(k)^2 + F(k+1)^2
+/// ```
+///
+/// starting from (F(0), F(1)) = (0, 1) and reading the bits of n from the most significant one:
+/// a bit b moves the state from (F(k), F(k+1)) to (F(2k + b), F(2k + b + 1)).
+///
+/// The bits are bound to n with the running sum of `DecomposeConfig` using 1-bit chunks:
+/// z_i = n >> i, so the prefixes of n read from the top are exactly z_C, z_{C-1}, ..., z_0.
+/// Copying them in reverse order next to the state, the bit consumed between two rows is
+/// b = z_next - 2 * z_cur, already range-checked by the decomposition lookup.
+///
+/// ```text
+/// |    z    |  f_k  |  f_k1  | q_double |
+/// ---------------------------------------
+/// |   z_C   |   0   |   1    |    1     |
+/// | z_{C-1} |  ...  |  ...   |    1     |
+/// |   ...   |  ...  |  ...   |   ...    |
+/// |   z_1   |  ...  |  ...   |    1     |
+/// |   z_0   |  F(n) | F(n+1) |    0     |
+/// ```
+#[derive(Debug, Clone)]
+pub struct FastFibonacciConfig<F: FieldExt + PrimeFieldBits> {
+    z: Column<Advice>,
+    f_k: Column<Advice>,
+    f_k1: Column<Advice>,
+    instance: Column<Instance>,
+    q_double: Selector,
+    decompose: DecomposeConfig<F, 1, 2>,
+}
+
+impl<F: FieldExt + PrimeFieldBits> FastFibonacciConfig<F> {
+    pub fn configure(meta: &mut ConstraintSystem<F>, tables: &mut TableRegistry<F>) -> Self {
+        let z = meta.advice_column();
+        let f_k = meta.advice_column();
+        let f_k1 = meta.advice_column();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(f_k);
+        meta.enable_equality(f_k1);
+        meta.enable_equality(instance);
+
+        //the running sum is decomposed in the same column its copies go to
+        let decompose = DecomposeConfig::configure(meta, z, tables);
+
+        let q_double = meta.selector();
+
+        meta.create_gate("fast doubling", |meta| {
+            let q_double = meta.query_selector(q_double);
+
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            let a = meta.query_advice(f_k, Rotation::cur());
+            let b = meta.query_advice(f_k1, Rotation::cur());
+            let a_next = meta.query_advice(f_k, Rotation::next());
+            let b_next = meta.query_advice(f_k1, Rotation::next());
+
+            let one = Expression::Constant(F::one());
+            let two = Expression::Constant(F::from(2));
+
+            // bit of n consumed by this step
+            let bit = z_next - z_cur * two.clone();
+
+            // F(2k) and F(2k + 1)
+            let even = a.clone() * (b.clone() * two - a.clone());
+            let odd = a.clone() * a + b.clone() * b;
+
+            Constraints::with_selector(
+                q_double,
+                [
+                    ("boolean bit", bit.clone() * (one - bit.clone())),
+                    (
+                        "F(2k + b)",
+                        a_next - (even.clone() + bit.clone() * (odd.clone() - even.clone())),
+                    ),
+                    ("F(2k + b + 1)", b_next - (odd + bit * even)),
+                ],
+            )
+        });
+
+        Self { z, f_k, f_k1, instance, q_double, decompose }
+    }
+
+    /// Returns the cells of n and F(n), n being range-checked to `num_bits` bits.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        n: Value<F>,
+        num_bits: usize,
+    ) -> Result<FastFibonacciCells<F>, Error> {
+        let n_cell = layouter.assign_region(
+            || "Witness n",
+            |mut region| region.assign_advice(|| "n", self.z, 0, || n.map(Assigned::from)),
+        )?;
+
+        let running_sum = self
+            .decompose
+            .assign(layouter.namespace(|| "decompose n"), n_cell.clone(), num_bits)?
+            .running_sum;
+
+        let out = layouter.assign_region(
+            || "fast doubling",
+            |mut region| {
+                //z_C = 0, so the state starts at (F(0), F(1))
+                let mut f_k = region.assign_advice_from_constant(
+                    || "F(0)",
+                    self.f_k,
+                    0,
+                    Assigned::from(F::zero()),
+                )?;
+                let mut f_k1 = region.assign_advice_from_constant(
+                    || "F(1)",
+                    self.f_k1,
+                    0,
+                    Assigned::from(F::one()),
+                )?;
+
+                let mut z_prev =
+                    running_sum.last().unwrap().copy_advice(|| "z_C", &mut region, self.z, 0)?;
+
+                for (row, z) in running_sum.iter().rev().enumerate().skip(1) {
+                    self.q_double.enable(&mut region, row - 1)?;
+
+                    let z_cur = z.copy_advice(|| "z", &mut region, self.z, row)?;
+
+                    let bit = z_cur
+                        .value()
+                        .zip(z_prev.value())
+                        .map(|(cur, prev)| *cur - *prev * Assigned::from(F::from(2)));
+
+                    let (even, odd) = f_k
+                        .value()
+                        .zip(f_k1.value())
+                        .map(|(a, b)| {
+                            let (a, b) = (*a, *b);
+                            (a * (b * F::from(2) - a), a * a + b * b)
+                        })
+                        .unzip();
+
+                    let next = bit.zip(even.zip(odd)).map(|(bit, (even, odd))| {
+                        if bit.evaluate() == F::one() {
+                            (odd, even + odd)
+                        } else {
+                            (even, odd)
+                        }
+                    });
+                    let (a_next, b_next) = next.unzip();
+
+                    f_k = region.assign_advice(|| "F(k)", self.f_k, row, || a_next)?;
+                    f_k1 = region.assign_advice(|| "F(k + 1)", self.f_k1, row, || b_next)?;
+                    z_prev = z_cur;
+                }
+
+                Ok(f_k.evaluate())
+            },
+        )?;
+
+        Ok(FastFibonacciCells { n: n_cell, out })
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: Cell,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell, self.instance, row)
+    }
+}
+
+/// Cells produced by `FastFibonacciConfig::assign`.
+#[derive(Debug, Clone)]
+pub struct FastFibonacciCells<F: FieldExt> {
+    /// The index n, first row of the running sum
+    pub n: AssignedCell<Assigned<F>, F>,
+    /// F(n), last row of the doubling region
+    pub out: AssignedCell<F, F>,
+}
+
+/// Circuit proving that the second public input is the Fibonacci number at the index given
+/// by the first public input, for indices of at most `num_bits` bits.
+///
+/// The index follows `FibonacciCircuit`: F(1) = F(2) = 1.
+#[derive(Debug, Clone)]
+pub struct FastFibonacciCircuit<F: FieldExt> {
+    pub n: Value<F>,
+    pub num_bits: usize,
+}
+
+impl<F: FieldExt + PrimeFieldBits> Circuit<F> for FastFibonacciCircuit<F> {
+    type Config = (FastFibonacciConfig<F>, TableRegistry<F>);
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self { n: Value::unknown(), num_bits: self.num_bits }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        // Fixed column for constants ==> z_C == 0 and the (0, 1) initial state
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+
+        let mut tables = TableRegistry::new();
+        (FastFibonacciConfig::configure(meta, &mut tables), tables)
+    }
+
+    fn synthesize(
+        &self,
+        (config, tables): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        tables.load(&mut layouter)?;
+
+        let cells = config.assign(layouter.namespace(|| "fast doubling"), self.n, self.num_bits)?;
+
+        config.expose_public(layouter.namespace(|| "n"), cells.n.cell(), 0)?;
+        config.expose_public(layouter.namespace(|| "out"), cells.out.cell(), 1)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    use super::*;
+    use crate::dev::assert_witness_sound;
+    use crate::fibonacci::fibonacci::FibonacciCircuit;
+
+    // native fast doubling, returning F(n)
+    fn fibonacci(n: u64) -> Fp {
+        let (mut a, mut b) = (Fp::zero(), Fp::one());
+        for i in (0..64).rev() {
+            let (even, odd) = (a * (b.double() - a), a.square() + b.square());
+            (a, b) = if (n >> i) & 1 == 1 { (odd, even + odd) } else { (even, odd) };
+        }
+        a
+    }
+
+    #[test]
+    fn test_fast_fibonacci() {
+        let k = 8;
+
+        for n in [0u64, 1, 2, 3, 10, 100, 1_000_000, u64::MAX] {
+            let circuit = FastFibonacciCircuit { n: Value::known(Fp::from(n)), num_bits: 64 };
+
+            let public_input = vec![Fp::from(n), fibonacci(n)];
+            let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+            prover.assert_satisfied();
+
+            let public_input = vec![Fp::from(n), fibonacci(n) + Fp::one()];
+            let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+
+        assert_eq!(fibonacci(10), Fp::from(55));
+    }
+
+    #[test]
+    fn test_fast_fibonacci_index_out_of_range() {
+        let k = 8;
+
+        // 2^16 does not fit in 16 bits
+        let n = 1 << 16;
+        let circuit = FastFibonacciCircuit { n: Value::known(Fp::from(n)), num_bits: 16 };
+
+        let public_input = vec![Fp::from(n), fibonacci(n)];
+        let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_fast_fibonacci_matches_row_per_term() {
+        // the row-per-term circuit needs 2^11 rows for n = 1000, 2^6 are enough here
+        for n in [3, 10, 100, 1000] {
+            let out = fibonacci(n as u64);
+
+            let circuit = FibonacciCircuit::<Fp>::new(n).unwrap();
+            let public_input = vec![Fp::one(), Fp::one(), out];
+            let prover = MockProver::run(circuit.k(), &circuit, vec![public_input]).unwrap();
+            prover.assert_satisfied();
+
+            let circuit =
+                FastFibonacciCircuit { n: Value::known(Fp::from(n as u64)), num_bits: 10 };
+            let public_input = vec![Fp::from(n as u64), out];
+            let prover = MockProver::run(6, &circuit, vec![public_input]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_fast_fibonacci_witness_sound() {
+        let circuit = FastFibonacciCircuit { n: Value::known(Fp::from(100)), num_bits: 8 };
+        assert_witness_sound(6, circuit, vec![vec![Fp::from(100), fibonacci(100)]]);
+    }
+}
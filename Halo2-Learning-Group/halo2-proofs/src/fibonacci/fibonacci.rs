@@ -1,45 +1,52 @@
-use std::marker::PhantomData;
+use std::{fmt, marker::PhantomData};
 
 use halo2_proofs::{
-    arithmetic::FieldExt, circuit::*, dev::MockProver, pasta::Fp, plonk::*, poly::Rotation,
+    arithmetic::FieldExt,
+    circuit::{floor_planner::V1, *},
+    plonk::*,
+    poly::Rotation,
 };
 
 /// One configuration for this gadget could look like:
 ///
-///     |      a     |        b      |        c       |      s       |
-///     -------------------------------------------------------------
-///     |       1     |       1       |       2       |      1       |
-///     |       1     |       2       |       3       |      1       |
-///     |       2     |       3       |       5       |      1       |
-///     |       3     |       5       |       8       |      1       |
-///     |     z_C     |       0       |      ...      |              |
+/// ```text
+/// |      a     |        b      |        c       |      s       |
+/// -------------------------------------------------------------
+/// |       1     |       1       |       2       |      1       |
+/// |       1     |       2       |       3       |      1       |
+/// |       2     |       3       |       5       |      1       |
+/// |       3     |       5       |       8       |      1       |
+/// |     z_C     |       0       |      ...      |              |
+/// ```
 ///
 ///
 /// Another configuration for this gadget could look like:
 ///
-///     |      a     |        s      
-///     ------------------------------
-///     |       1     |       1     
-///     |       1     |            
-///     |       2     |             
-///     |       1     |       1      
-///     |       2     |         
-///     |       3     |         
-///     |       2     |       1  
-///     |       3     |         
-///     |       5     |         
-///     |       3     |       1  
+/// ```text
+/// |      a     |        s      
+/// ------------------------------
+/// |       1     |       1     
+/// |       1     |            
+/// |       2     |             
+/// |       1     |       1      
+/// |       2     |         
+/// |       3     |         
+/// |       2     |       1  
+/// |       3     |         
+/// |       5     |         
+/// |       3     |       1  
+/// ```
 
 #[derive(Debug, Clone)]
-struct FibonacciConfig<F: FieldExt, const FIBONACCI_NUMBER: usize> {
+pub struct FibonacciConfig<F: FieldExt> {
     advice: Column<Advice>,
     instance: Column<Instance>,
     selector: Selector,
     _marker: PhantomData<F>,
 }
 
-impl<F: FieldExt, const FIBONACCI_NUMBER: usize> FibonacciConfig<F, FIBONACCI_NUMBER> {
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+impl<F: FieldExt> FibonacciConfig<F> {
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
         //allocating our only advice column
         let advice = meta.advice_column();
 
@@ -69,13 +76,15 @@ impl<F: FieldExt, const FIBONACCI_NUMBER: usize> FibonacciConfig<F, FIBONACCI_NU
         Self { advice, instance, selector, _marker: PhantomData }
     }
 
-    fn assign(&self, mut layouter: impl Layouter<F>) -> Result<AssignedCell<F, F>, Error> {
+    /// Returns the cells of all `num_terms` terms, the two seeds included.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        num_terms: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
         layouter.assign_region(
             || "Fibonacci Column",
             |mut region| {
-                //enabling selector at first row, index 0
-                self.selector.enable(&mut region, 0)?;
-
                 //Assigning first fibo numbers
                 let mut a_cell = region.assign_advice_from_instance(
                     || "Assign first Numbe: 1",
@@ -91,21 +100,21 @@ impl<F: FieldExt, const FIBONACCI_NUMBER: usize> FibonacciConfig<F, FIBONACCI_NU
                     self.advice,
                     1,
                 )?;
+                let mut terms = vec![a_cell.clone(), b_cell.clone()];
 
-                for row in 2..FIBONACCI_NUMBER {
-                    //remember selector is only switched on every three
-                    if row < FIBONACCI_NUMBER - 2 {
-                        self.selector.enable(&mut region, row)?;
-                    }
+                for row in 2..num_terms {
+                    //the gate two rows above checks this term is the sum of the previous two
+                    self.selector.enable(&mut region, row - 2)?;
 
                     let c_val = a_cell.value().and_then(|a| b_cell.value().map(|b| *a + *b));
 
                     let c_cell = region.assign_advice(|| "c_value", self.advice, row, || c_val)?;
+                    terms.push(c_cell.clone());
 
                     a_cell = b_cell;
                     b_cell = c_cell;
                 }
-                Ok(b_cell)
+                Ok(terms)
             },
         )
     }
@@ -120,67 +129,452 @@ impl<F: FieldExt, const FIBONACCI_NUMBER: usize> FibonacciConfig<F, FIBONACCI_NU
     }
 }
 
+/// Error returned when a Fibonacci circuit cannot be built for the requested length.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FibonacciError {
+    /// The gate spans three rows, so at least three terms are needed.
+    TooFewTerms(usize),
+    /// A linear recurrence of order d needs the d seeds and at least one computed term.
+    TooFewRecurrenceTerms { num_terms: usize, order: usize },
+    /// The terms and the blinding rows do not fit in the largest domain of the field.
+    TooManyTerms { num_terms: usize, max_terms: usize },
+    /// A public term index is not below the number of terms, or the step of
+    /// `PublicTerms::EveryNth` is zero.
+    InvalidPublicTerm { index: usize, num_terms: usize },
+}
+
+impl fmt::Display for FibonacciError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooFewTerms(num_terms) => {
+                write!(f, "a Fibonacci circuit needs at least 3 terms, got {}", num_terms)
+            }
+            Self::TooFewRecurrenceTerms { num_terms, order } => write!(
+                f,
+                "a recurrence of order {} needs at least {} terms, got {}",
+                order,
+                order + 1,
+                num_terms
+            ),
+            Self::TooManyTerms { num_terms, max_terms } => write!(
+                f,
+                "{} Fibonacci terms do not fit in the circuit, at most {} are supported",
+                num_terms, max_terms
+            ),
+            Self::InvalidPublicTerm { index, num_terms } => write!(
+                f,
+                "cannot expose term {} of a Fibonacci circuit with {} terms",
+                index, num_terms
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FibonacciError {}
+
+/// Which terms a `FibonacciCircuit` exposes after its two seeds, by 0-based index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PublicTerms {
+    /// Only the last term
+    Last,
+    /// Terms n - 1, 2n - 1, ..., i.e. every n-th term
+    EveryNth(usize),
+    /// An explicit list of indices, exposed in the given order
+    Indices(Vec<usize>),
+}
+
+impl PublicTerms {
+    /// Resolves the indices of the exposed terms for a sequence of `num_terms` terms.
+    pub fn indices(&self, num_terms: usize) -> Result<Vec<usize>, FibonacciError> {
+        let indices = match self {
+            Self::Last => vec![num_terms - 1],
+            Self::EveryNth(0) => {
+                return Err(FibonacciError::InvalidPublicTerm { index: 0, num_terms })
+            }
+            Self::EveryNth(n) => (n - 1..num_terms).step_by(*n).collect(),
+            Self::Indices(indices) => indices.clone(),
+        };
+
+        match indices.iter().find(|index| **index >= num_terms) {
+            Some(&index) => Err(FibonacciError::InvalidPublicTerm { index, num_terms }),
+            None => Ok(indices),
+        }
+    }
+}
+
+/// Public inputs of a `FibonacciCircuit`.
+///
+/// ```text
+/// | instance |
+/// ------------
+/// |    a     |  row 0
+/// |    b     |  row 1
+/// |  t_{i0}  |  row 2, first index of the `PublicTerms`
+/// |  t_{i1}  |  row 3
+/// |   ...    |
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FibonacciInstance<F: FieldExt> {
+    pub a: F,
+    pub b: F,
+    /// The exposed terms along with their index, in instance order
+    pub terms: Vec<(usize, F)>,
+}
+
+impl<F: FieldExt> FibonacciInstance<F> {
+    /// The instance column, to be passed to the prover and the verifier.
+    pub fn to_vec(&self) -> Vec<F> {
+        [self.a, self.b].into_iter().chain(self.terms.iter().map(|(_, term)| *term)).collect()
+    }
+}
+
+/// Circuit proving that the `num_terms`-th term of the sequence seeded with the first two
+/// public inputs is equal to the third public input.
+///
+/// The length is part of the circuit shape, so it is kept by `without_witnesses` and two
+/// circuits of different lengths have different verifying keys. The same goes for the
+/// exposed terms, by default only the last one, see `with_public_terms`.
+#[derive(Debug, Clone)]
+pub struct FibonacciCircuit<F: FieldExt> {
+    num_terms: usize,
+    public_terms: Vec<usize>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FibonacciCircuit<F> {
+    pub fn new(num_terms: usize) -> Result<Self, FibonacciError> {
+        if num_terms < 3 {
+            return Err(FibonacciError::TooFewTerms(num_terms));
+        }
+
+        //the evaluation domain of the field bounds the number of rows, 2^S
+        let max_terms = (1usize << F::S) - Self::unusable_rows();
+        if num_terms > max_terms {
+            return Err(FibonacciError::TooManyTerms { num_terms, max_terms });
+        }
+
+        Ok(Self { num_terms, public_terms: vec![num_terms - 1], _marker: PhantomData })
+    }
+
+    /// Exposes the selected terms instead of the last one.
+    pub fn with_public_terms(mut self, public_terms: PublicTerms) -> Result<Self, FibonacciError> {
+        self.public_terms = public_terms.indices(self.num_terms)?;
+        Ok(self)
+    }
+
+    pub fn num_terms(&self) -> usize {
+        self.num_terms
+    }
+
+    /// Indices of the exposed terms, in instance order from row 2.
+    pub fn public_terms(&self) -> &[usize] {
+        &self.public_terms
+    }
+
+    /// Builds the public inputs for the sequence seeded with `a` and `b`, claiming the exposed
+    /// terms are the ones returned by `term`.
+    pub fn instance(&self, a: F, b: F, term: impl Fn(usize) -> F) -> FibonacciInstance<F> {
+        let terms = self.public_terms.iter().map(|&index| (index, term(index))).collect();
+        FibonacciInstance { a, b, terms }
+    }
+
+    /// Smallest k such that the 2^k rows hold every term, as well as the instance column,
+    /// plus the rows reserved for blinding.
+    pub fn k(&self) -> u32 {
+        let rows = self.num_terms.max(2 + self.public_terms.len()) + Self::unusable_rows();
+        usize::BITS - (rows - 1).leading_zeros()
+    }
+
+    //rows at the end of the column the prover fills with random values, plus the last row
+    fn unusable_rows() -> usize {
+        let mut meta = ConstraintSystem::default();
+        Self::configure(&mut meta);
+        meta.blinding_factors() + 1
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for FibonacciCircuit<F> {
+    type Config = FibonacciConfig<F>;
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FibonacciConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let terms = config.assign(layouter.namespace(|| "Assign value"), self.num_terms)?;
+
+        for (row, &index) in self.public_terms.iter().enumerate() {
+            config.expose_public(layouter.namespace(|| "out"), terms[index].clone(), 2 + row)?;
+        }
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use halo2_proofs::{
-        circuit::floor_planner::V1,
-        dev::{FailureLocation, MockProver, VerifyFailure},
-        pasta::Fp,
-        plonk::{Any, Circuit},
-    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    use crate::proving::{prove, setup, verify};
 
     use super::*;
+    use crate::dev::assert_witness_sound;
+
+    fn fibonacci(num_terms: usize) -> Fp {
+        let (mut a, mut b) = (Fp::one(), Fp::one());
+        for _ in 2..num_terms {
+            (a, b) = (b, a + b);
+        }
+        b
+    }
+
+    /// A prover shifting term `shift.0` by `shift.1` and keeping the following terms sums of
+    /// the previous two
+    struct ForgedCircuit {
+        circuit: FibonacciCircuit<Fp>,
+        shift: (usize, Fp),
+    }
 
-    #[derive(Default)]
-    struct MyCircuit<F: FieldExt, const FIBONACCI_NUMBER: usize> {
-        _marker: PhantomData<F>,
+    impl ForgedCircuit {
+        /// The forged terms seeded with 1, 1
+        fn terms(&self) -> Vec<Fp> {
+            let mut terms = vec![Fp::one(), Fp::one()];
+            for index in 2..self.circuit.num_terms {
+                let mut term = terms[index - 2] + terms[index - 1];
+                if index == self.shift.0 {
+                    term += self.shift.1;
+                }
+                terms.push(term);
+            }
+            terms
+        }
+
+        /// The public inputs matching the forged terms
+        fn public_input(&self) -> Vec<Fp> {
+            let terms = self.terms();
+            self.circuit.instance(terms[0], terms[1], |index| terms[index]).to_vec()
+        }
     }
 
-    impl<F: FieldExt, const FIBONACCI_NUMBER: usize> Circuit<F> for MyCircuit<F, FIBONACCI_NUMBER> {
-        type Config = FibonacciConfig<F, FIBONACCI_NUMBER>;
+    impl Circuit<Fp> for ForgedCircuit {
+        type Config = FibonacciConfig<Fp>;
         type FloorPlanner = V1;
 
         fn without_witnesses(&self) -> Self {
-            println!("no witness");
-            Self::default()
+            Self { circuit: self.circuit.clone(), shift: self.shift }
         }
 
-        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
             FibonacciConfig::configure(meta)
         }
 
         fn synthesize(
             &self,
             config: Self::Config,
-            mut layouter: impl Layouter<F>,
+            mut layouter: impl Layouter<Fp>,
         ) -> Result<(), Error> {
-            let out_cell = config.assign(layouter.namespace(|| "Assign value"))?;
-
-            config.expose_public(layouter.namespace(|| "out"), out_cell, 2)?;
-
+            // the layout of `FibonacciConfig::assign` with the forged values
+            let values = self.terms();
+            let terms = layouter.assign_region(
+                || "Fibonacci Column",
+                |mut region| {
+                    let mut terms = vec![];
+                    for (row, value) in values.iter().enumerate() {
+                        let cell = if row < 2 {
+                            region.assign_advice_from_instance(
+                                || "seed",
+                                config.instance,
+                                row,
+                                config.advice,
+                                row,
+                            )?
+                        } else {
+                            config.selector.enable(&mut region, row - 2)?;
+                            region.assign_advice(
+                                || "c_value",
+                                config.advice,
+                                row,
+                                || Value::known(*value),
+                            )?
+                        };
+                        terms.push(cell);
+                    }
+                    Ok(terms)
+                },
+            )?;
+
+            for (row, &index) in self.circuit.public_terms.iter().enumerate() {
+                config.expose_public(
+                    layouter.namespace(|| "out"),
+                    terms[index].clone(),
+                    2 + row,
+                )?;
+            }
             Ok(())
         }
     }
 
+    fn verify_forged(circuit: &FibonacciCircuit<Fp>, shift: (usize, Fp)) -> Result<(), ()> {
+        let forged = ForgedCircuit { circuit: circuit.clone(), shift };
+        let prover = MockProver::run(circuit.k(), &forged, vec![forged.public_input()]).unwrap();
+        prover.verify().map_err(|_| ())
+    }
+
     #[test]
     fn test_fibonacci() {
-        let k = 4;
-
         let a = Fp::from(1);
         let b = Fp::from(1);
         let out = Fp::from(55);
 
-        const FIBONACCI_NUMBER: usize = 10;
-
-        let circuit = MyCircuit::<Fp, FIBONACCI_NUMBER> { _marker: PhantomData };
+        let circuit = FibonacciCircuit::<Fp>::new(10).unwrap();
+        assert_eq!(circuit.k(), 4);
 
         let public_input = vec![a, b, out];
 
-        let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+        let prover = MockProver::run(circuit.k(), &circuit, vec![public_input]).unwrap();
+
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_fibonacci_lengths() {
+        for num_terms in [3, 4, 11, 100, 1000] {
+            let circuit = FibonacciCircuit::<Fp>::new(num_terms).unwrap();
+
+            let public_input = vec![Fp::one(), Fp::one(), fibonacci(num_terms)];
+
+            let prover = MockProver::run(circuit.k(), &circuit, vec![public_input]).unwrap();
+            prover.assert_satisfied();
+
+            // The derived k must be the smallest one that fits
+            let prover = MockProver::run(
+                circuit.k() - 1,
+                &circuit,
+                vec![vec![Fp::one(), Fp::one(), fibonacci(num_terms)]],
+            );
+            assert!(prover.is_err());
+        }
+    }
+
+    #[test]
+    fn test_fibonacci_forged_output() {
+        // 1, 1, 2, 3: t3 is only checked by the gate at row 1
+        let circuit = FibonacciCircuit::<Fp>::new(4).unwrap();
+        assert_eq!(verify_forged(&circuit, (3, Fp::zero())), Ok(()));
+        assert!(verify_forged(&circuit, (3, Fp::one())).is_err());
+    }
+
+    #[test]
+    fn test_fibonacci_length_errors() {
+        assert_eq!(FibonacciCircuit::<Fp>::new(2).unwrap_err(), FibonacciError::TooFewTerms(2));
+
+        let err = FibonacciCircuit::<Fp>::new(1 << 32).unwrap_err();
+        assert!(
+            matches!(err, FibonacciError::TooManyTerms { num_terms, .. } if num_terms == 1 << 32)
+        );
+    }
+
+    #[test]
+    fn test_fibonacci_public_terms() {
+        let terms: Vec<Fp> = (1..=12).map(fibonacci).collect();
+
+        // 3rd, 6th, 9th and 12th terms
+        let circuit =
+            FibonacciCircuit::<Fp>::new(12).unwrap().with_public_terms(PublicTerms::EveryNth(3));
+        let circuit = circuit.unwrap();
+        assert_eq!(circuit.public_terms(), &[2, 5, 8, 11]);
+
+        let instance = circuit.instance(Fp::one(), Fp::one(), |index| terms[index]);
+        assert_eq!(instance.to_vec()[2..], [Fp::from(2), Fp::from(8), Fp::from(34), Fp::from(144)]);
+
+        let prover = MockProver::run(circuit.k(), &circuit, vec![instance.to_vec()]).unwrap();
+        prover.assert_satisfied();
+
+        // Any wrong intermediate term is rejected
+        let mut wrong = instance.clone();
+        wrong.terms[1].1 += Fp::one();
+        let prover = MockProver::run(circuit.k(), &circuit, vec![wrong.to_vec()]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // An explicit list, in any order and with the seeds
+        let circuit = FibonacciCircuit::<Fp>::new(12)
+            .unwrap()
+            .with_public_terms(PublicTerms::Indices(vec![7, 0, 3]))
+            .unwrap();
+        let instance = circuit.instance(Fp::one(), Fp::one(), |index| terms[index]);
+        let prover = MockProver::run(circuit.k(), &circuit, vec![instance.to_vec()]).unwrap();
+        prover.assert_satisfied();
 
+        // Exposing every term needs more instance rows than there are terms
+        let circuit = FibonacciCircuit::<Fp>::new(12)
+            .unwrap()
+            .with_public_terms(PublicTerms::EveryNth(1))
+            .unwrap();
+        let instance = circuit.instance(Fp::one(), Fp::one(), |index| terms[index]);
+        let prover = MockProver::run(circuit.k(), &circuit, vec![instance.to_vec()]).unwrap();
         prover.assert_satisfied();
     }
 
+    #[test]
+    fn test_fibonacci_forged_public_terms() {
+        // 1, 1, 2, 100, 102, 202 instead of 1, 1, 2, 3, 5, 8, every term exposed
+        for public_terms in [PublicTerms::EveryNth(1), PublicTerms::Indices(vec![3, 5])] {
+            let circuit =
+                FibonacciCircuit::<Fp>::new(6).unwrap().with_public_terms(public_terms).unwrap();
+            assert_eq!(verify_forged(&circuit, (3, Fp::zero())), Ok(()));
+            assert!(verify_forged(&circuit, (3, Fp::from(97))).is_err());
+        }
+    }
+
+    #[test]
+    fn test_fibonacci_public_terms_errors() {
+        let circuit = FibonacciCircuit::<Fp>::new(10).unwrap();
+
+        let err = circuit.clone().with_public_terms(PublicTerms::Indices(vec![3, 10])).unwrap_err();
+        assert_eq!(err, FibonacciError::InvalidPublicTerm { index: 10, num_terms: 10 });
+
+        let err = circuit.with_public_terms(PublicTerms::EveryNth(0)).unwrap_err();
+        assert_eq!(err, FibonacciError::InvalidPublicTerm { index: 0, num_terms: 10 });
+    }
+
+    #[test]
+    fn test_fibonacci_ipa() {
+        let circuit = FibonacciCircuit::<Fp>::new(10).unwrap();
+
+        let (params, pk) = setup(circuit.k(), &circuit).unwrap();
+
+        let public_input = vec![Fp::from(1), Fp::from(1), Fp::from(55)];
+
+        let proof = prove(&params, &pk, circuit, &[&public_input]).unwrap();
+        verify(&params, pk.get_vk(), &proof, &[&public_input]).unwrap();
+
+        // The proof must not verify against a different claimed output
+        let wrong_input = vec![Fp::from(1), Fp::from(1), Fp::from(56)];
+        assert!(verify(&params, pk.get_vk(), &proof, &[&wrong_input]).is_err());
+    }
+
+    #[test]
+    fn test_fibonacci_ipa_wrong_output() {
+        let circuit = FibonacciCircuit::<Fp>::new(10).unwrap();
+        let (params, pk) = setup(circuit.k(), &circuit).unwrap();
+
+        // An honest prover can still produce a transcript for a false statement,
+        // but it must be rejected by the verifier
+        let public_input = vec![Fp::from(1), Fp::from(1), Fp::from(56)];
+        let proof = prove(&params, &pk, circuit, &[&public_input]).unwrap();
+
+        assert!(verify(&params, pk.get_vk(), &proof, &[&public_input]).is_err());
+    }
+
     #[cfg(feature = "dev-graph")]
     #[test]
     fn plot_fibonacci() {
@@ -190,10 +584,21 @@ mod tests {
         root.fill(&WHITE).unwrap();
         let root = root.titled("Fibonacci Layout", ("sans-serif", 60)).unwrap();
 
-        const FIBONACCI_NUMBER: usize = 10;
+        let circuit = FibonacciCircuit::<Fp>::new(10).unwrap();
 
-        let circuit = MyCircuit::<Fp, FIBONACCI_NUMBER> { _marker: PhantomData };
+        halo2_proofs::dev::CircuitLayout::default().render(circuit.k(), &circuit, &root).unwrap();
+    }
 
-        halo2_proofs::dev::CircuitLayout::default().render(4, &circuit, &root).unwrap();
+    #[test]
+    fn test_fibonacci_witness_sound() {
+        let circuit = FibonacciCircuit::<Fp>::new(10).unwrap();
+        let k = circuit.k();
+        assert_witness_sound(k, circuit.clone(), vec![vec![Fp::one(), Fp::one(), Fp::from(55)]]);
+
+        // a forged term carried through the following ones is missed by the harness, every
+        // term must be checked by its own gate
+        for index in 2..10 {
+            assert!(verify_forged(&circuit, (index, Fp::one())).is_err(), "term {}", index);
+        }
     }
 }
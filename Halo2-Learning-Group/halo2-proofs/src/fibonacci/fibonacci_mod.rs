@@ -0,0 +1,340 @@
+use ff::PrimeFieldBits;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{floor_planner::V1, *},
+    plonk::*,
+    poly::Rotation,
+};
+
+use super::fibonacci::FibonacciError;
+use crate::range_check::{decompose_range_check::DecomposeConfig, table::TableRegistry};
+
+/// Bit length of the supported moduli, m ≤ 2^MODULUS_BITS
+pub const MODULUS_BITS: usize = 32;
+
+/// Whether 1 ≤ m ≤ 2^MODULUS_BITS, the moduli the remainder bound is sound for
+fn is_supported_modulus<F: FieldExt>(m: F) -> bool {
+    let low = m.get_lower_128();
+    F::from_u128(low) == m && (1..=1 << MODULUS_BITS).contains(&low)
+}
+
+/// Fibonacci sequence reduced modulo a public m, one row per term.
+///
+/// Given two reduced terms a, b < m, their sum is below 2m so it is reduced with a single
+/// boolean quotient q:
+///
+/// ```text
+/// a + b = q * m + r,    q ∈ {0, 1}
+/// ```
+///
+/// and r < m is enforced by range-checking both r and its slack s = m - 1 - r to
+/// MODULUS_BITS bits with `DecomposeConfig`, which is sound as long as 1 ≤ m ≤ 2^MODULUS_BITS.
+/// That is enforced too, by range-checking m - 1 to MODULUS_BITS bits in the otherwise empty
+/// quotient cell of the first row. The seeds go through the same bound as the terms, so every
+/// term is the true integer remainder.
+///
+/// ```text
+/// |  value  | quotient | modulus |  slack  | q_step | q_bound | q_modulus |
+/// -------------------------------------------------------------------------
+/// |    a    |   m-1    |    m    | m-1-a   |   1    |    1    |     1     |
+/// |    b    |          |    m    | m-1-b   |   1    |    1    |           |
+/// |    r    |    q     |    m    | m-1-r   |  ...   |    1    |           |
+/// |   ...   |   ...    |   ...   |   ...   |        |   ...   |           |
+/// ```
+///
+/// The modulus column is copied from the instance on every row.
+#[derive(Debug, Clone)]
+pub struct FibonacciModConfig<F: FieldExt + PrimeFieldBits> {
+    value: Column<Advice>,
+    quotient: Column<Advice>,
+    modulus: Column<Advice>,
+    slack: Column<Advice>,
+    instance: Column<Instance>,
+    q_step: Selector,
+    q_bound: Selector,
+    q_modulus: Selector,
+    decompose: DecomposeConfig<F, 8, 256>,
+}
+
+impl<F: FieldExt + PrimeFieldBits> FibonacciModConfig<F> {
+    pub fn configure(meta: &mut ConstraintSystem<F>, tables: &mut TableRegistry<F>) -> Self {
+        let value = meta.advice_column();
+        let quotient = meta.advice_column();
+        let modulus = meta.advice_column();
+        let slack = meta.advice_column();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(value);
+        meta.enable_equality(quotient);
+        meta.enable_equality(modulus);
+        meta.enable_equality(slack);
+        meta.enable_equality(instance);
+
+        let running_sum = meta.advice_column();
+        let decompose = DecomposeConfig::configure(meta, running_sum, tables);
+
+        let q_step = meta.selector();
+        let q_bound = meta.selector();
+        let q_modulus = meta.selector();
+
+        meta.create_gate("Fibonacci mod m", |meta| {
+            let q_step = meta.query_selector(q_step);
+
+            let a = meta.query_advice(value, Rotation::cur());
+            let b = meta.query_advice(value, Rotation::next());
+            let r = meta.query_advice(value, Rotation(2));
+            let q = meta.query_advice(quotient, Rotation(2));
+            let m = meta.query_advice(modulus, Rotation(2));
+
+            let one = Expression::Constant(F::one());
+
+            Constraints::with_selector(
+                q_step,
+                [
+                    ("reduction", a + b - q.clone() * m - r),
+                    ("boolean quotient", q.clone() * (one - q)),
+                ],
+            )
+        });
+
+        meta.create_gate("remainder bound", |meta| {
+            let q_bound = meta.query_selector(q_bound);
+
+            let r = meta.query_advice(value, Rotation::cur());
+            let m = meta.query_advice(modulus, Rotation::cur());
+            let s = meta.query_advice(slack, Rotation::cur());
+
+            // s = m - 1 - r, with both s and r range-checked
+            vec![q_bound * (m - Expression::Constant(F::one()) - r - s)]
+        });
+
+        meta.create_gate("modulus bound", |meta| {
+            let q_modulus = meta.query_selector(q_modulus);
+
+            let m = meta.query_advice(modulus, Rotation::cur());
+            let m_minus_one = meta.query_advice(quotient, Rotation::cur());
+
+            // m - 1 is range-checked, so 1 ≤ m ≤ 2^MODULUS_BITS
+            vec![q_modulus * (m - Expression::Constant(F::one()) - m_minus_one)]
+        });
+
+        Self { value, quotient, modulus, slack, instance, q_step, q_bound, q_modulus, decompose }
+    }
+
+    /// Assigns `num_terms` terms seeded with instance rows 0 and 1, reduced modulo instance
+    /// row 2, and returns the last one.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        num_terms: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let (m_minus_one, terms, slacks) = layouter.assign_region(
+            || "Fibonacci mod m",
+            |mut region| {
+                let mut m_minus_one = None;
+                let mut terms: Vec<AssignedCell<F, F>> = vec![];
+                let mut slacks = vec![];
+
+                for row in 0..num_terms {
+                    if row < num_terms - 2 {
+                        self.q_step.enable(&mut region, row)?;
+                    }
+                    self.q_bound.enable(&mut region, row)?;
+
+                    let m = region.assign_advice_from_instance(
+                        || "m",
+                        self.instance,
+                        2,
+                        self.modulus,
+                        row,
+                    )?;
+                    // m is divided by below, so the prover rejects it before the gates do
+                    m.value().error_if_known_and(|m| !is_supported_modulus(**m))?;
+
+                    if row == 0 {
+                        self.q_modulus.enable(&mut region, row)?;
+                        let value = m.value().map(|m| *m - F::one());
+                        m_minus_one =
+                            Some(region.assign_advice(|| "m - 1", self.quotient, row, || value)?);
+                    }
+
+                    let r_cell = if row < 2 {
+                        region.assign_advice_from_instance(
+                            || "seed",
+                            self.instance,
+                            row,
+                            self.value,
+                            row,
+                        )?
+                    } else {
+                        let (a, b) = (&terms[row - 2], &terms[row - 1]);
+
+                        //a + b < 2m < 2^64, so the reduction is done over u128
+                        let reduced = a.value().zip(b.value()).zip(m.value()).map(|((a, b), m)| {
+                            let sum = (*a + *b).get_lower_128();
+                            let m = m.get_lower_128();
+                            (F::from_u128(sum / m), F::from_u128(sum % m))
+                        });
+                        let (q, r) = reduced.unzip();
+
+                        region.assign_advice(|| "q", self.quotient, row, || q)?;
+                        region.assign_advice(|| "r", self.value, row, || r)?
+                    };
+
+                    let s = r_cell.value().zip(m.value()).map(|(r, m)| *m - F::one() - *r);
+                    let s_cell = region.assign_advice(|| "m - 1 - r", self.slack, row, || s)?;
+
+                    terms.push(r_cell);
+                    slacks.push(s_cell);
+                }
+
+                Ok((m_minus_one.unwrap(), terms, slacks))
+            },
+        )?;
+
+        // range-check m - 1, every term and its slack
+        for (i, cell) in std::iter::once(&m_minus_one).chain(&terms).chain(&slacks).enumerate() {
+            self.decompose.assign(
+                layouter.namespace(|| format!("range check {}", i)),
+                cell.clone(),
+                MODULUS_BITS,
+            )?;
+        }
+
+        Ok(terms[num_terms - 1].clone())
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.instance, row)
+    }
+}
+
+/// Circuit proving that the fourth public input is the `num_terms`-th term of the Fibonacci
+/// sequence seeded with the first two public inputs, reduced modulo the third one.
+///
+/// The circuit only accepts reduced seeds and a modulus 1 ≤ m ≤ 2^MODULUS_BITS, so the
+/// verifier does not need to check the public inputs itself.
+#[derive(Debug, Clone)]
+pub struct FibonacciModCircuit<F: FieldExt> {
+    num_terms: usize,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> FibonacciModCircuit<F> {
+    pub fn new(num_terms: usize) -> Result<Self, FibonacciError> {
+        if num_terms < 3 {
+            return Err(FibonacciError::TooFewTerms(num_terms));
+        }
+
+        Ok(Self { num_terms, _marker: std::marker::PhantomData })
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits> Circuit<F> for FibonacciModCircuit<F> {
+    type Config = (FibonacciModConfig<F>, TableRegistry<F>);
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        // Fixed column for constants ==> z_C == 0
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+
+        let mut tables = TableRegistry::new();
+        (FibonacciModConfig::configure(meta, &mut tables), tables)
+    }
+
+    fn synthesize(
+        &self,
+        (config, tables): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        tables.load(&mut layouter)?;
+
+        let out = config.assign(layouter.namespace(|| "Fibonacci mod m"), self.num_terms)?;
+
+        config.expose_public(layouter.namespace(|| "out"), &out, 3)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    use super::*;
+    use crate::dev::assert_witness_sound;
+
+    fn fibonacci_mod(a: u64, b: u64, m: u64, num_terms: usize) -> u64 {
+        let (mut a, mut b) = (a, b);
+        for _ in 2..num_terms {
+            (a, b) = (b, (a + b) % m);
+        }
+        b
+    }
+
+    fn public_input(a: u64, b: u64, m: u64, out: u64) -> Vec<Vec<Fp>> {
+        vec![vec![Fp::from(a), Fp::from(b), Fp::from(m), Fp::from(out)]]
+    }
+
+    #[test]
+    fn test_fibonacci_mod() {
+        let k = 10;
+
+        for (m, num_terms) in [(1 << 32, 100), (1_000_000_007, 60), (7, 20), (2, 5)] {
+            let circuit = FibonacciModCircuit::<Fp>::new(num_terms).unwrap();
+
+            let out = fibonacci_mod(1, 1, m, num_terms);
+            let prover = MockProver::run(k, &circuit, public_input(1, 1, m, out)).unwrap();
+            prover.assert_satisfied();
+
+            // the unreduced sum is a valid field element but not the remainder
+            let prover = MockProver::run(k, &circuit, public_input(1, 1, m, out + m)).unwrap();
+            assert!(prover.verify().is_err());
+        }
+
+        // F(100) does not fit in 64 bits, so this wraps several times
+        assert_eq!(fibonacci_mod(1, 1, 1 << 32, 100), 3_314_859_971);
+    }
+
+    #[test]
+    fn test_fibonacci_mod_unreduced_seed() {
+        let k = 10;
+        let circuit = FibonacciModCircuit::<Fp>::new(10).unwrap();
+
+        // 9 ≡ 2 mod 7, but seeds must be reduced
+        let out = fibonacci_mod(1, 2, 7, 10);
+        let prover = MockProver::run(k, &circuit, public_input(1, 9, 7, out)).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_fibonacci_mod_unsupported_modulus() {
+        let k = 10;
+        let circuit = FibonacciModCircuit::<Fp>::new(10).unwrap();
+
+        // the prover refuses to divide rather than panicking
+        for m in [0, (1 << 32) + 1] {
+            let prover = MockProver::run(k, &circuit, public_input(1, 1, m, 0));
+            assert!(matches!(prover, Err(Error::Synthesis)));
+        }
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::one(), Fp::one(), -Fp::one()]]);
+        assert!(matches!(prover, Err(Error::Synthesis)));
+    }
+
+    #[test]
+    fn test_fibonacci_mod_witness_sound() {
+        let circuit = FibonacciModCircuit::<Fp>::new(10).unwrap();
+        let out = fibonacci_mod(3, 5, 11, 10);
+        assert_witness_sound(10, circuit, public_input(3, 5, 11, out));
+    }
+}
@@ -0,0 +1,321 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{floor_planner::V1, *},
+    plonk::*,
+    poly::Rotation,
+};
+
+use super::fibonacci::FibonacciError;
+
+/// Generalisation of the Fibonacci gate to any linear recurrence of order d:
+///
+/// ```text
+/// x_n = c_1 * x_{n-1} + c_2 * x_{n-2} + ... + c_d * x_{n-d}
+/// ```
+///
+/// The terms live in a single advice column, as in `FibonacciConfig`, and each coefficient
+/// c_j gets its own fixed column, filled on every row the selector is enabled on.
+/// For d = 3 (Tribonacci) one configuration could look like:
+///
+/// ```text
+/// | advice |  c_1  |  c_2  |  c_3  |  s  |
+/// ---------------------------------------
+/// |    0   |   1   |   1   |   1   |  1  |
+/// |    0   |   1   |   1   |   1   |  1  |
+/// |    1   |   1   |   1   |   1   |  1  |
+/// |    1   |       |       |       |     |
+/// |    2   |       |       |       |     |
+/// |    4   |       |       |       |     |
+/// ```
+///
+/// The gate enabled at row i checks c_1 * x_{i+d-1} + ... + c_d * x_i - x_{i+d} = 0.
+/// The first d terms are copied from the instance column, and the output is exposed
+/// at instance row d.
+#[derive(Debug, Clone)]
+pub struct LinearRecurrenceConfig<F: FieldExt> {
+    advice: Column<Advice>,
+    instance: Column<Instance>,
+    // (c_j, column holding c_j), j = 1..=d
+    coefficients: Vec<(F, Column<Fixed>)>,
+    selector: Selector,
+}
+
+impl<F: FieldExt> LinearRecurrenceConfig<F> {
+    /// `coefficients` are c_1..c_d, the order being their number.
+    pub fn configure(meta: &mut ConstraintSystem<F>, coefficients: Vec<F>) -> Self {
+        assert!(!coefficients.is_empty(), "a recurrence needs at least one coefficient");
+
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+
+        let coefficients: Vec<_> =
+            coefficients.into_iter().map(|c| (c, meta.fixed_column())).collect();
+
+        let selector = meta.selector();
+
+        let order = coefficients.len() as i32;
+        let columns: Vec<_> = coefficients.iter().map(|(_, column)| *column).collect();
+
+        meta.create_gate("linear recurrence", |meta| {
+            let s = meta.query_selector(selector);
+
+            // Σ_j c_j * x_{n-j}, with x_n sitting at Rotation(d)
+            let combination = columns.iter().enumerate().fold(
+                Expression::Constant(F::zero()),
+                |acc, (j, column)| {
+                    let c = meta.query_fixed(*column, Rotation::cur());
+                    let x = meta.query_advice(advice, Rotation(order - 1 - j as i32));
+                    acc + c * x
+                },
+            );
+            let next = meta.query_advice(advice, Rotation(order));
+
+            vec![s * (combination - next)]
+        });
+
+        Self { advice, instance, coefficients, selector }
+    }
+
+    pub fn order(&self) -> usize {
+        self.coefficients.len()
+    }
+
+    /// Assigns `num_terms` terms and returns the last one.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        num_terms: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let order = self.order();
+        assert!(num_terms > order, "the recurrence must compute at least one term");
+
+        layouter.assign_region(
+            || "Linear recurrence column",
+            |mut region| {
+                //the first d terms are the public seeds
+                let mut terms = (0..order)
+                    .map(|row| {
+                        region.assign_advice_from_instance(
+                            || format!("seed {}", row),
+                            self.instance,
+                            row,
+                            self.advice,
+                            row,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+
+                for row in order..num_terms {
+                    //the gate computing x_row is enabled d rows above it
+                    let offset = row - order;
+                    self.selector.enable(&mut region, offset)?;
+                    for (c, column) in self.coefficients.iter() {
+                        region.assign_fixed(
+                            || "coefficient",
+                            *column,
+                            offset,
+                            || Value::known(*c),
+                        )?;
+                    }
+
+                    let value = self
+                        .coefficients
+                        .iter()
+                        .enumerate()
+                        .fold(Value::known(F::zero()), |acc, (j, (c, _))| {
+                            acc + terms[row - 1 - j].value().map(|x| *c * *x)
+                        });
+
+                    terms.push(region.assign_advice(|| "term", self.advice, row, || value)?);
+                }
+
+                Ok(terms.pop().unwrap())
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.instance, row)
+    }
+}
+
+/// Coefficients c_1..c_d of a linear recurrence, known when the circuit is configured.
+pub trait RecurrenceSpec {
+    fn coefficients<F: FieldExt>() -> Vec<F>;
+}
+
+/// x_n = x_{n-1} + x_{n-2}, giving the Fibonacci or Lucas numbers depending on the seeds.
+#[derive(Debug, Clone)]
+pub struct Fibonacci;
+
+impl RecurrenceSpec for Fibonacci {
+    fn coefficients<F: FieldExt>() -> Vec<F> {
+        vec![F::one(), F::one()]
+    }
+}
+
+/// x_n = x_{n-1} + x_{n-2} + x_{n-3}
+#[derive(Debug, Clone)]
+pub struct Tribonacci;
+
+impl RecurrenceSpec for Tribonacci {
+    fn coefficients<F: FieldExt>() -> Vec<F> {
+        vec![F::one(), F::one(), F::one()]
+    }
+}
+
+/// x_n = 2 * x_{n-1} + x_{n-2}
+#[derive(Debug, Clone)]
+pub struct Pell;
+
+impl RecurrenceSpec for Pell {
+    fn coefficients<F: FieldExt>() -> Vec<F> {
+        vec![F::from(2), F::one()]
+    }
+}
+
+/// Circuit proving that the `num_terms`-th term of the recurrence `S` seeded with the first
+/// d public inputs is equal to public input d.
+#[derive(Debug)]
+pub struct LinearRecurrenceCircuit<F: FieldExt, S: RecurrenceSpec> {
+    pub num_terms: usize,
+    _marker: PhantomData<(F, S)>,
+}
+
+impl<F: FieldExt, S: RecurrenceSpec> LinearRecurrenceCircuit<F, S> {
+    pub fn new(num_terms: usize) -> Result<Self, FibonacciError> {
+        let order = S::coefficients::<F>().len();
+        if num_terms <= order {
+            return Err(FibonacciError::TooFewRecurrenceTerms { num_terms, order });
+        }
+
+        Ok(Self { num_terms, _marker: PhantomData })
+    }
+}
+
+impl<F: FieldExt, S: RecurrenceSpec> Circuit<F> for LinearRecurrenceCircuit<F, S> {
+    type Config = LinearRecurrenceConfig<F>;
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self { num_terms: self.num_terms, _marker: PhantomData }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        LinearRecurrenceConfig::configure(meta, S::coefficients())
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let out_cell = config.assign(layouter.namespace(|| "Assign value"), self.num_terms)?;
+
+        config.expose_public(layouter.namespace(|| "out"), out_cell, config.order())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    use super::*;
+    use crate::dev::assert_witness_sound;
+
+    // native evaluation of the recurrence, returning the `num_terms`-th term
+    fn recurrence<S: RecurrenceSpec>(seeds: &[u64], num_terms: usize) -> Fp {
+        let coefficients = S::coefficients::<Fp>();
+        let mut terms: Vec<Fp> = seeds.iter().map(|s| Fp::from(*s)).collect();
+        for n in seeds.len()..num_terms {
+            let next = coefficients
+                .iter()
+                .enumerate()
+                .fold(Fp::zero(), |acc, (j, c)| acc + *c * terms[n - 1 - j]);
+            terms.push(next);
+        }
+        terms[num_terms - 1]
+    }
+
+    fn check<S: RecurrenceSpec>(seeds: &[u64], num_terms: usize, out: u64) {
+        let k = 5;
+
+        assert_eq!(recurrence::<S>(seeds, num_terms), Fp::from(out));
+
+        let circuit = LinearRecurrenceCircuit::<Fp, S>::new(num_terms).unwrap();
+
+        let mut public_input: Vec<_> = seeds.iter().map(|s| Fp::from(*s)).collect();
+        public_input.push(Fp::from(out));
+        let prover = MockProver::run(k, &circuit, vec![public_input.clone()]).unwrap();
+        prover.assert_satisfied();
+
+        // Wrong output
+        *public_input.last_mut().unwrap() += Fp::one();
+        let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_fibonacci() {
+        check::<Fibonacci>(&[1, 1], 10, 55);
+    }
+
+    #[test]
+    fn test_lucas() {
+        // 2, 1, 3, 4, 7, 11, 18, 29, 47, 76
+        check::<Fibonacci>(&[2, 1], 10, 76);
+    }
+
+    #[test]
+    fn test_tribonacci() {
+        // 0, 0, 1, 1, 2, 4, 7, 13, 24, 44
+        check::<Tribonacci>(&[0, 0, 1], 10, 44);
+    }
+
+    #[test]
+    fn test_pell() {
+        // 0, 1, 2, 5, 12, 29, 70, 169, 408, 985
+        check::<Pell>(&[0, 1], 10, 985);
+    }
+
+    #[test]
+    fn test_long_recurrence() {
+        let k = 8;
+        let num_terms = 200;
+
+        let circuit = LinearRecurrenceCircuit::<Fp, Tribonacci>::new(num_terms).unwrap();
+        let out = recurrence::<Tribonacci>(&[0, 0, 1], num_terms);
+        let public_input = vec![Fp::zero(), Fp::zero(), Fp::one(), out];
+
+        let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_linear_recurrence_length_errors() {
+        let err = LinearRecurrenceCircuit::<Fp, Tribonacci>::new(3).unwrap_err();
+        assert_eq!(err, FibonacciError::TooFewRecurrenceTerms { num_terms: 3, order: 3 });
+
+        assert!(LinearRecurrenceCircuit::<Fp, Pell>::new(2).is_err());
+        assert!(LinearRecurrenceCircuit::<Fp, Pell>::new(3).is_ok());
+    }
+
+    #[test]
+    fn test_linear_recurrence_witness_sound() {
+        let circuit = LinearRecurrenceCircuit::<Fp, Tribonacci>::new(10).unwrap();
+        let public_input = vec![Fp::zero(), Fp::zero(), Fp::one(), Fp::from(44)];
+        assert_witness_sound(5, circuit, vec![public_input]);
+    }
+}
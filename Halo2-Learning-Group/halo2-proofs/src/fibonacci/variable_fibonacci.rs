@@ -0,0 +1,361 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{floor_planner::V1, *},
+    plonk::*,
+    poly::Rotation,
+};
+
+/// Fibonacci circuit where the index n of the exposed term is a private witness.
+///
+/// All MAX_TERMS terms are always computed, so the verifying key only depends on the
+/// maximum. An indicator column selects the n-th term:
+///
+/// ```text
+/// b_i ∈ {0, 1},     Σ b_i = 1,     Σ i * b_i = n,     out = Σ b_i * F(i)
+/// ```
+///
+/// The three sums are accumulated row by row, and the last row holds their totals.
+/// Since exactly one indicator is set and positions only run over 1..=MAX_TERMS, this
+/// also proves that 1 ≤ n ≤ MAX_TERMS.
+///
+/// ```text
+/// | value | indicator | acc_indicator | acc_index | acc_out | position | q_fib | q_first | q_acc |
+/// ---------------------------------------------------------------------------------------------
+/// |   1   |     0     |       0       |     0     |    0    |    1     |   1   |    1    |   0   |
+/// |   1   |     0     |       0       |     0     |    0    |    2     |   1   |    0    |   1   |
+/// |   2   |     1     |       1       |     3     |    2    |    3     |   1   |    0    |   1   |
+/// |   3   |     0     |       1       |     3     |    2    |    4     |   0   |    0    |   1   |
+/// |   5   |     0     |       1       |     3     |    2    |    5     |   0   |    0    |   1   |
+/// ```
+///
+/// Here n = 3 is hidden while out = 2 is exposed.
+#[derive(Debug, Clone)]
+pub struct VariableFibonacciConfig<F: FieldExt, const MAX_TERMS: usize> {
+    value: Column<Advice>,
+    indicator: Column<Advice>,
+    acc_indicator: Column<Advice>,
+    acc_index: Column<Advice>,
+    acc_out: Column<Advice>,
+    position: Column<Fixed>,
+    instance: Column<Instance>,
+    q_fib: Selector,
+    q_first: Selector,
+    q_acc: Selector,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const MAX_TERMS: usize> VariableFibonacciConfig<F, MAX_TERMS> {
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let value = meta.advice_column();
+        let indicator = meta.advice_column();
+        let acc_indicator = meta.advice_column();
+        let acc_index = meta.advice_column();
+        let acc_out = meta.advice_column();
+        let position = meta.fixed_column();
+        let instance = meta.instance_column();
+
+        meta.enable_equality(value);
+        meta.enable_equality(acc_indicator);
+        meta.enable_equality(acc_index);
+        meta.enable_equality(acc_out);
+        meta.enable_equality(instance);
+
+        let q_fib = meta.selector();
+        let q_first = meta.selector();
+        let q_acc = meta.selector();
+
+        meta.create_gate("Fibonacci", |meta| {
+            let a = meta.query_advice(value, Rotation::cur());
+            let b = meta.query_advice(value, Rotation::next());
+            let c = meta.query_advice(value, Rotation(2));
+
+            let s = meta.query_selector(q_fib);
+
+            vec![s * (a + b - c)]
+        });
+
+        //the first row starts every sum, the following ones add to the row above
+        for (name, selector, rotation) in
+            [("first selection", q_first, None), ("next selection", q_acc, Some(Rotation::prev()))]
+        {
+            meta.create_gate(name, |meta| {
+                let s = meta.query_selector(selector);
+
+                let value = meta.query_advice(value, Rotation::cur());
+                let b = meta.query_advice(indicator, Rotation::cur());
+                let position = meta.query_fixed(position, Rotation::cur());
+
+                let acc_indicator_cur = meta.query_advice(acc_indicator, Rotation::cur());
+                let acc_index_cur = meta.query_advice(acc_index, Rotation::cur());
+                let acc_out_cur = meta.query_advice(acc_out, Rotation::cur());
+
+                let (acc_indicator_prev, acc_index_prev, acc_out_prev) = match rotation {
+                    Some(rotation) => (
+                        meta.query_advice(acc_indicator, rotation),
+                        meta.query_advice(acc_index, rotation),
+                        meta.query_advice(acc_out, rotation),
+                    ),
+                    None => (
+                        Expression::Constant(F::zero()),
+                        Expression::Constant(F::zero()),
+                        Expression::Constant(F::zero()),
+                    ),
+                };
+
+                let one = Expression::Constant(F::one());
+
+                Constraints::with_selector(
+                    s,
+                    [
+                        ("boolean indicator", b.clone() * (one - b.clone())),
+                        ("sum indicators", acc_indicator_cur - (acc_indicator_prev + b.clone())),
+                        ("sum indices", acc_index_cur - (acc_index_prev + position * b.clone())),
+                        ("select term", acc_out_cur - (acc_out_prev + b * value)),
+                    ],
+                )
+            });
+        }
+
+        Self {
+            value,
+            indicator,
+            acc_indicator,
+            acc_index,
+            acc_out,
+            position,
+            instance,
+            q_fib,
+            q_first,
+            q_acc,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Computes all MAX_TERMS terms and returns the cells of the selected term and of n.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        n: Value<usize>,
+    ) -> Result<SelectedTerm<F>, Error> {
+        assert!(MAX_TERMS >= 3);
+
+        layouter.assign_region(
+            || "Variable Fibonacci",
+            |mut region| {
+                //first two terms are copied from the instance column, as in FibonacciConfig
+                let mut terms = vec![
+                    region.assign_advice_from_instance(
+                        || "first term",
+                        self.instance,
+                        0,
+                        self.value,
+                        0,
+                    )?,
+                    region.assign_advice_from_instance(
+                        || "second term",
+                        self.instance,
+                        1,
+                        self.value,
+                        1,
+                    )?,
+                ];
+
+                for row in 2..MAX_TERMS {
+                    let c_val = terms[row - 2]
+                        .value()
+                        .and_then(|a| terms[row - 1].value().map(|b| *a + *b));
+                    terms.push(region.assign_advice(|| "term", self.value, row, || c_val)?);
+                }
+
+                let mut acc_indicator = Value::known(F::zero());
+                let mut acc_index = Value::known(F::zero());
+                let mut acc_out = Value::known(F::zero());
+                let mut last = None;
+
+                for (row, term) in terms.iter().enumerate() {
+                    if row < MAX_TERMS - 2 {
+                        self.q_fib.enable(&mut region, row)?;
+                    }
+                    if row == 0 {
+                        self.q_first.enable(&mut region, row)?;
+                    } else {
+                        self.q_acc.enable(&mut region, row)?;
+                    }
+
+                    //terms are 1-indexed, F(1) sits on row 0
+                    let position = F::from((row + 1) as u64);
+                    region.assign_fixed(
+                        || "position",
+                        self.position,
+                        row,
+                        || Value::known(position),
+                    )?;
+
+                    let b = n.map(|n| if n == row + 1 { F::one() } else { F::zero() });
+                    region.assign_advice(|| "indicator", self.indicator, row, || b)?;
+
+                    acc_indicator = acc_indicator + b;
+                    acc_index = acc_index + b.map(|b| b * position);
+                    acc_out = acc_out + b.zip(term.value()).map(|(b, term)| b * term);
+
+                    let acc_indicator_cell = region.assign_advice(
+                        || "acc_indicator",
+                        self.acc_indicator,
+                        row,
+                        || acc_indicator,
+                    )?;
+                    let acc_index_cell =
+                        region.assign_advice(|| "acc_index", self.acc_index, row, || acc_index)?;
+                    let acc_out_cell =
+                        region.assign_advice(|| "acc_out", self.acc_out, row, || acc_out)?;
+
+                    last = Some((acc_indicator_cell, acc_index_cell, acc_out_cell));
+                }
+
+                let (acc_indicator_cell, acc_index_cell, acc_out_cell) = last.unwrap();
+
+                //exactly one indicator is set
+                region.constrain_constant(acc_indicator_cell.cell(), F::one())?;
+
+                Ok(SelectedTerm { out: acc_out_cell, n: acc_index_cell })
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.instance, row)
+    }
+}
+
+/// Cells produced by `VariableFibonacciConfig::assign`.
+#[derive(Debug, Clone)]
+pub struct SelectedTerm<F: FieldExt> {
+    /// The n-th term, total of the selection accumulator
+    pub out: AssignedCell<F, F>,
+    /// The private index n, total of the index accumulator
+    pub n: AssignedCell<F, F>,
+}
+
+/// Circuit proving that the third public input is the n-th term of the sequence seeded with
+/// the first two public inputs, for some private n ≤ MAX_TERMS.
+#[derive(Debug, Clone)]
+pub struct VariableFibonacciCircuit<F: FieldExt, const MAX_TERMS: usize> {
+    pub n: Value<usize>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const MAX_TERMS: usize> VariableFibonacciCircuit<F, MAX_TERMS> {
+    pub fn new(n: usize) -> Self {
+        Self { n: Value::known(n), _marker: PhantomData }
+    }
+}
+
+impl<F: FieldExt, const MAX_TERMS: usize> Circuit<F> for VariableFibonacciCircuit<F, MAX_TERMS> {
+    type Config = VariableFibonacciConfig<F, MAX_TERMS>;
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self {
+        Self { n: Value::unknown(), _marker: PhantomData }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        // Fixed column for constants ==> Σ b_i == 1
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+
+        VariableFibonacciConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let selected = config.assign(layouter.namespace(|| "Assign value"), self.n)?;
+
+        config.expose_public(layouter.namespace(|| "out"), selected.out, 2)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    use crate::proving::{prove, setup, verify};
+
+    use super::*;
+    use crate::dev::assert_witness_sound;
+
+    const MAX_TERMS: usize = 10;
+
+    fn fibonacci(n: usize) -> Fp {
+        let (mut a, mut b) = (Fp::zero(), Fp::one());
+        for _ in 1..n {
+            (a, b) = (b, a + b);
+        }
+        b
+    }
+
+    #[test]
+    fn test_variable_fibonacci() {
+        let k = 5;
+
+        for n in 1..=MAX_TERMS {
+            let circuit = VariableFibonacciCircuit::<Fp, MAX_TERMS>::new(n);
+
+            let public_input = vec![Fp::one(), Fp::one(), fibonacci(n)];
+            let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+            prover.assert_satisfied();
+
+            // Claiming any other output for the same index must fail
+            let public_input = vec![Fp::one(), Fp::one(), fibonacci(n) + Fp::one()];
+            let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_variable_fibonacci_out_of_range() {
+        let k = 5;
+
+        // No indicator is set for an index outside of 1..=MAX_TERMS
+        for n in [0, MAX_TERMS + 1] {
+            let circuit = VariableFibonacciCircuit::<Fp, MAX_TERMS>::new(n);
+
+            let public_input = vec![Fp::one(), Fp::one(), Fp::zero()];
+            let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_variable_fibonacci_single_vk() {
+        let k = 5;
+
+        let (params, pk) = setup(k, &VariableFibonacciCircuit::<Fp, MAX_TERMS>::new(1)).unwrap();
+
+        // The same keys prove statements for every hidden index
+        for n in [3, 7, MAX_TERMS] {
+            let circuit = VariableFibonacciCircuit::<Fp, MAX_TERMS>::new(n);
+            let public_input = vec![Fp::one(), Fp::one(), fibonacci(n)];
+
+            let proof = prove(&params, &pk, circuit, &[&public_input]).unwrap();
+            verify(&params, pk.get_vk(), &proof, &[&public_input]).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_variable_fibonacci_witness_sound() {
+        let circuit = VariableFibonacciCircuit::<Fp, MAX_TERMS>::new(7);
+        assert_witness_sound(5, circuit, vec![vec![Fp::one(), Fp::one(), fibonacci(7)]]);
+    }
+}
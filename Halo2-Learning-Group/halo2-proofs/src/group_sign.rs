@@ -0,0 +1,351 @@
+//! Group signatures from the circom `GroupSign` and `MerkleGroupSign` templates: a proof
+//! that the signer knows a secret key sk whose public key pk = Poseidon(sk) belongs to a
+//! group, for a public message m.
+//!
+//! ```text
+//! GroupSignCircuit       : Π (pk_i - pk) = 0  over the public keys pk_0 .. pk_{n-1}
+//! MerkleGroupSignCircuit : pk is a leaf of the tree with the public root
+//! ```
+//!
+//! The running product takes a row per key and the keys as public inputs, the Merkle
+//! variant a hash per level and only the root. Circom binds m with a dummy m * m
+//! constraint; here m is copied from its instance column into the circuit, and the
+//! instance is part of the transcript, so a proof does not verify for another message.
+//!
+//! Public inputs, one instance column each:
+//!
+//! ```text
+//! GroupSignCircuit       : [m], [pk_0, .., pk_{n-1}]
+//! MerkleGroupSignCircuit : [m], [root]
+//! ```
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{Circuit, Column, ConstraintSystem, Error, Fixed, Instance, Selector},
+    poly::Rotation,
+};
+
+use crate::{
+    merkle::{MerkleChip, MerkleConfig, MerkleInstructions},
+    poseidon::{chip::hash, P128Pow5T3, Pow5Chip, Pow5Config},
+};
+
+/// Witnesses sk and returns Poseidon(sk), as the circom `SecretToPublic` template.
+fn secret_to_public<F: FieldExt>(
+    config: &Pow5Config<F, 3, 2>,
+    mut layouter: impl Layouter<F>,
+    sk: Value<F>,
+) -> Result<AssignedCell<F, F>, Error> {
+    let sk = layouter.assign_region(
+        || "Witness sk",
+        |mut region| region.assign_advice(|| "sk", config.state[0], 0, || sk),
+    )?;
+
+    let chip = Pow5Chip::construct(config.clone());
+    hash(chip, layouter.namespace(|| "pk = H(sk)"), &[sk])
+}
+
+/// Copies the message into the circuit.
+fn bind_message<F: FieldExt>(
+    config: &Pow5Config<F, 3, 2>,
+    mut layouter: impl Layouter<F>,
+    message: Column<Instance>,
+) -> Result<AssignedCell<F, F>, Error> {
+    layouter.assign_region(
+        || "message",
+        |mut region| region.assign_advice_from_instance(|| "m", message, 0, config.state[0], 0),
+    )
+}
+
+/// The running product on the Poseidon state columns:
+///
+/// ```text
+/// | state_0  | state_1 | state_2 | q_product |
+/// --------------------------------------------
+/// |  pk_0    |   pk    |    1    |     1     |
+/// |  pk_1    |   pk    |  acc_1  |     1     |
+/// |   ...    |   ...   |   ...   |    ...    |
+/// | pk_{n-1} |   pk    | acc_n-1 |     1     |
+/// |          |         |    0    |     0     |
+/// ```
+///
+/// with acc_{i+1} = acc_i * (pk_i - pk), the first and last accumulators being constants.
+#[derive(Debug, Clone)]
+pub struct GroupSignConfig<F: FieldExt> {
+    pub constants: Column<Fixed>,
+    poseidon: Pow5Config<F, 3, 2>,
+    q_product: Selector,
+    message: Column<Instance>,
+    public_keys: Column<Instance>,
+}
+
+/// Proves Poseidon(sk) is one of the `num_keys` public keys.
+#[derive(Debug, Clone)]
+pub struct GroupSignCircuit<F: FieldExt> {
+    pub sk: Value<F>,
+    pub num_keys: usize,
+}
+
+impl<F: FieldExt> GroupSignCircuit<F> {
+    pub fn new(sk: F, num_keys: usize) -> Self {
+        Self { sk: Value::known(sk), num_keys }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for GroupSignCircuit<F> {
+    type Config = GroupSignConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { sk: Value::unknown(), num_keys: self.num_keys }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+
+        let state = [(); 3].map(|_| meta.advice_column());
+        let poseidon = Pow5Chip::configure::<P128Pow5T3>(meta, state);
+
+        let message = meta.instance_column();
+        let public_keys = meta.instance_column();
+        meta.enable_equality(message);
+        meta.enable_equality(public_keys);
+
+        let q_product = meta.selector();
+
+        meta.create_gate("running product", |meta| {
+            let q_product = meta.query_selector(q_product);
+
+            let pk_i = meta.query_advice(state[0], Rotation::cur());
+            let pk = meta.query_advice(state[1], Rotation::cur());
+            let acc_cur = meta.query_advice(state[2], Rotation::cur());
+            let acc_next = meta.query_advice(state[2], Rotation::next());
+
+            vec![q_product * (acc_next - acc_cur * (pk_i - pk))]
+        });
+
+        GroupSignConfig { constants, poseidon, q_product, message, public_keys }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        bind_message(&config.poseidon, layouter.namespace(|| "m"), config.message)?;
+
+        let pk = secret_to_public(&config.poseidon, layouter.namespace(|| "pk"), self.sk)?;
+        let state = config.poseidon.state;
+
+        layouter.assign_region(
+            || "running product",
+            |mut region| {
+                let mut acc =
+                    region.assign_advice_from_constant(|| "acc_0", state[2], 0, F::one())?;
+
+                for i in 0..self.num_keys {
+                    config.q_product.enable(&mut region, i)?;
+
+                    let pk_i = region.assign_advice_from_instance(
+                        || "pk_i",
+                        config.public_keys,
+                        i,
+                        state[0],
+                        i,
+                    )?;
+                    let pk = pk.copy_advice(|| "pk", &mut region, state[1], i)?;
+
+                    let next = acc
+                        .value()
+                        .zip(pk_i.value())
+                        .zip(pk.value())
+                        .map(|((acc, pk_i), pk)| *acc * (*pk_i - pk));
+                    acc = region.assign_advice(|| "acc", state[2], i + 1, || next)?;
+                }
+
+                // pk is one of the keys
+                region.constrain_constant(acc.cell(), F::zero())
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MerkleGroupSignConfig<F: FieldExt> {
+    pub constants: Column<Fixed>,
+    merkle: MerkleConfig<F>,
+    message: Column<Instance>,
+    root: Column<Instance>,
+}
+
+/// Proves Poseidon(sk) is a leaf of the tree with the public root, at the position given by
+/// the private path.
+#[derive(Debug, Clone)]
+pub struct MerkleGroupSignCircuit<F: FieldExt> {
+    pub sk: Value<F>,
+    pub siblings: Vec<Value<F>>,
+    /// 1 if the sibling is on the left
+    pub path_indices: Vec<Value<F>>,
+}
+
+impl<F: FieldExt> MerkleGroupSignCircuit<F> {
+    pub fn new(sk: F, siblings: &[F], path_indices: &[bool]) -> Self {
+        Self {
+            sk: Value::known(sk),
+            siblings: siblings.iter().map(|s| Value::known(*s)).collect(),
+            path_indices: path_indices.iter().map(|b| Value::known(F::from(*b as u64))).collect(),
+        }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MerkleGroupSignCircuit<F> {
+    type Config = MerkleGroupSignConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            sk: Value::unknown(),
+            siblings: vec![Value::unknown(); self.siblings.len()],
+            path_indices: vec![Value::unknown(); self.path_indices.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+
+        let state = [(); 3].map(|_| meta.advice_column());
+        let merkle = MerkleChip::configure(meta, state);
+
+        let message = meta.instance_column();
+        let root = meta.instance_column();
+        meta.enable_equality(message);
+        meta.enable_equality(root);
+
+        MerkleGroupSignConfig { constants, merkle, message, root }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let poseidon = &config.merkle.poseidon;
+        bind_message(poseidon, layouter.namespace(|| "m"), config.message)?;
+
+        let pk = secret_to_public(poseidon, layouter.namespace(|| "pk"), self.sk)?;
+
+        let chip = MerkleChip::construct(config.merkle.clone());
+        let root = chip.calculate_root(
+            layouter.namespace(|| "merkle"),
+            &pk,
+            &self.siblings,
+            &self.path_indices,
+        )?;
+
+        layouter.constrain_instance(root.cell(), config.root, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    use super::*;
+    use crate::{
+        dev::assert_witness_sound,
+        merkle::{MerkleTree, PoseidonHasher},
+        proving::{prove, setup, verify},
+    };
+
+    /// The public keys of secret keys 1..=n
+    fn public_keys(hasher: &PoseidonHasher<Fp>, n: u64) -> Vec<Fp> {
+        (1..=n).map(|sk| hasher.hash(&[Fp::from(sk)])).collect()
+    }
+
+    #[test]
+    fn test_group_sign() {
+        let k = 8;
+        let hasher = PoseidonHasher::new();
+        let keys = public_keys(&hasher, 4);
+        let m = Fp::from(1234);
+
+        for sk in 1..=4 {
+            let circuit = GroupSignCircuit::new(Fp::from(sk), keys.len());
+            let prover = MockProver::run(k, &circuit, vec![vec![m], keys.clone()]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // a key outside of the group
+        let circuit = GroupSignCircuit::new(Fp::from(5), keys.len());
+        let prover = MockProver::run(k, &circuit, vec![vec![m], keys.clone()]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // the key itself not being listed
+        let mut others = keys.clone();
+        others[1] = hasher.hash(&[Fp::from(9)]);
+        let circuit = GroupSignCircuit::new(Fp::from(2), keys.len());
+        let prover = MockProver::run(k, &circuit, vec![vec![m], others]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_merkle_group_sign() {
+        let k = 9;
+        let mut tree = MerkleTree::new(3);
+        for sk in 1..=5 {
+            tree.insert(sk, tree.hasher().hash(&[Fp::from(sk)]));
+        }
+        let m = Fp::from(1234);
+
+        for sk in 1..=5 {
+            let path = tree.path(sk);
+            let circuit =
+                MerkleGroupSignCircuit::new(Fp::from(sk), &path.siblings, &path.path_indices);
+            let prover = MockProver::run(k, &circuit, vec![vec![m], vec![tree.root()]]).unwrap();
+            prover.assert_satisfied();
+        }
+
+        // a secret key whose public key is not at the leaf
+        let path = tree.path(2);
+        let circuit = MerkleGroupSignCircuit::new(Fp::from(3), &path.siblings, &path.path_indices);
+        let prover = MockProver::run(k, &circuit, vec![vec![m], vec![tree.root()]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // another root
+        let circuit = MerkleGroupSignCircuit::new(Fp::from(2), &path.siblings, &path.path_indices);
+        let prover = MockProver::run(k, &circuit, vec![vec![m], vec![tree.root() + Fp::one()]]);
+        assert!(prover.unwrap().verify().is_err());
+    }
+
+    #[test]
+    fn test_group_sign_binds_message() {
+        let k = 8;
+        let keys = public_keys(&PoseidonHasher::new(), 3);
+        let circuit = GroupSignCircuit::new(Fp::from(2), keys.len());
+
+        let (params, pk) = setup(k, &circuit).unwrap();
+        let m = [Fp::from(1234)];
+        let proof = prove(&params, &pk, circuit, &[&m, &keys]).unwrap();
+
+        assert!(verify(&params, pk.get_vk(), &proof, &[&m, &keys]).is_ok());
+        let other = [Fp::from(1235)];
+        assert!(verify(&params, pk.get_vk(), &proof, &[&other, &keys]).is_err());
+    }
+
+    #[test]
+    fn test_group_sign_witness_sound() {
+        let hasher = PoseidonHasher::new();
+        let keys = public_keys(&hasher, 2);
+        let circuit = GroupSignCircuit::new(Fp::from(2), keys.len());
+        assert_witness_sound(8, circuit, vec![vec![Fp::from(1234)], keys]);
+
+        let mut tree = MerkleTree::new(1);
+        tree.insert(1, hasher.hash(&[Fp::from(2)]));
+        let path = tree.path(1);
+        let circuit = MerkleGroupSignCircuit::new(Fp::from(2), &path.siblings, &path.path_indices);
+        assert_witness_sound(8, circuit, vec![vec![Fp::from(1234)], vec![tree.root()]]);
+    }
+}
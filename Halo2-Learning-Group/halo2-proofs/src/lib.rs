@@ -1,2 +1,10 @@
-mod fibonacci;
-mod range_check;
+#[cfg(test)]
+mod dev;
+pub mod ecc;
+pub mod fibonacci;
+pub mod group_sign;
+pub mod logic;
+pub mod merkle;
+pub mod poseidon;
+pub mod proving;
+pub mod range_check;
@@ -0,0 +1,7 @@
+pub mod is_equal;
+pub mod is_zero;
+pub mod mux;
+
+pub use is_equal::{IsEqualChip, IsEqualConfig, IsEqualInstructions};
+pub use is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstructions};
+pub use mux::{MuxChip, MuxConfig, MuxInstructions, OutOfRange};
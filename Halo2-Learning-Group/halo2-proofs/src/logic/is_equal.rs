@@ -0,0 +1,187 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter},
+    plonk::{Advice, Column, ConstraintSystem, Error, Selector},
+    poly::Rotation,
+};
+
+use super::is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstructions};
+
+/// Tests two cells for equality, as the circom `IsEqual` template.
+pub trait IsEqualInstructions<F: FieldExt> {
+    /// 1 if a = b, 0 otherwise
+    fn is_equal(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error>;
+}
+
+/// is_equal(a, b) = is_zero(a - b), the difference being constrained in its own region
+/// and copied into the `IsZeroChip`:
+///
+/// ```text
+/// | lhs | rhs |  diff  | q_diff |
+/// -------------------------------
+/// |  a  |  b  | a - b  |   1    |
+/// ```
+#[derive(Debug, Clone)]
+pub struct IsEqualConfig<F: FieldExt> {
+    lhs: Column<Advice>,
+    rhs: Column<Advice>,
+    diff: Column<Advice>,
+    q_diff: Selector,
+    is_zero: IsZeroConfig<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IsEqualChip<F: FieldExt> {
+    config: IsEqualConfig<F>,
+}
+
+impl<F: FieldExt> IsEqualChip<F> {
+    pub fn construct(config: IsEqualConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        lhs: Column<Advice>,
+        rhs: Column<Advice>,
+    ) -> IsEqualConfig<F> {
+        let diff = meta.advice_column();
+        meta.enable_equality(lhs);
+        meta.enable_equality(rhs);
+        meta.enable_equality(diff);
+
+        let q_diff = meta.selector();
+
+        meta.create_gate("diff", |meta| {
+            let q_diff = meta.query_selector(q_diff);
+
+            let a = meta.query_advice(lhs, Rotation::cur());
+            let b = meta.query_advice(rhs, Rotation::cur());
+            let diff = meta.query_advice(diff, Rotation::cur());
+
+            vec![q_diff * (diff - (a - b))]
+        });
+
+        // the difference is copied into the value column of the is-zero check
+        let is_zero = IsZeroChip::configure(meta, diff);
+
+        IsEqualConfig { lhs, rhs, diff, q_diff, is_zero }
+    }
+}
+
+impl<F: FieldExt> IsEqualInstructions<F> for IsEqualChip<F> {
+    fn is_equal(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+
+        let diff = layouter.assign_region(
+            || "diff",
+            |mut region| {
+                config.q_diff.enable(&mut region, 0)?;
+
+                a.copy_advice(|| "a", &mut region, config.lhs, 0)?;
+                b.copy_advice(|| "b", &mut region, config.rhs, 0)?;
+
+                let diff = a.value().zip(b.value()).map(|(a, b)| *a - *b);
+                region.assign_advice(|| "a - b", config.diff, 0, || diff)
+            },
+        )?;
+
+        IsZeroChip::construct(config.is_zero.clone())
+            .is_zero(layouter.namespace(|| "is zero"), &diff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{floor_planner::V1, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::dev::assert_witness_sound;
+
+    /// Exposes is_equal(a, b) of two private inputs
+    #[derive(Clone, Default)]
+    struct MyCircuit {
+        a: Value<Fp>,
+        b: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for MyCircuit {
+        type Config = (IsEqualConfig<Fp>, Column<Instance>);
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let lhs = meta.advice_column();
+            let rhs = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            (IsEqualChip::configure(meta, lhs, rhs), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let (a, b) = layouter.assign_region(
+                || "Witness inputs",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", config.lhs, 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", config.rhs, 0, || self.b)?;
+                    Ok((a, b))
+                },
+            )?;
+
+            let chip = IsEqualChip::construct(config);
+            let out = chip.is_equal(layouter.namespace(|| "is equal"), &a, &b)?;
+
+            layouter.constrain_instance(out.cell(), instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_is_equal() {
+        let k = 4;
+
+        // IsEqual with "in": ["0", "2"]
+        for (a, b, out) in [
+            (Fp::zero(), Fp::from(2), Fp::zero()),
+            (Fp::from(2), Fp::from(2), Fp::one()),
+            (-Fp::one(), Fp::one(), Fp::zero()),
+        ] {
+            let circuit = MyCircuit { a: Value::known(a), b: Value::known(b) };
+
+            let prover = MockProver::run(k, &circuit, vec![vec![out]]).unwrap();
+            prover.assert_satisfied();
+
+            // a malicious prover cannot claim the opposite
+            let prover = MockProver::run(k, &circuit, vec![vec![Fp::one() - out]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_is_equal_witness_sound() {
+        let circuit = MyCircuit { a: Value::known(Fp::zero()), b: Value::known(Fp::from(2)) };
+        assert_witness_sound(4, circuit, vec![vec![Fp::zero()]]);
+    }
+}
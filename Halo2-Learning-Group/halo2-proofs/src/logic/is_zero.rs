@@ -0,0 +1,238 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+/// Tests a cell against zero, as the circom `IsZero` template.
+pub trait IsZeroInstructions<F: FieldExt> {
+    /// 1 if x = 0, 0 otherwise
+    fn is_zero(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error>;
+}
+
+/// The prover witnesses inv = 1/x, or 0 if x = 0, and the output is constrained by:
+///
+/// ```text
+/// out = 1 - x * inv
+/// x * out = 0
+/// ```
+///
+/// If x ≠ 0 the second constraint forces out = 0, and if x = 0 the first one forces
+/// out = 1 whatever inv is, so out is boolean without a constraint of its own.
+///
+/// ```text
+/// | value | inv | out | q_is_zero |
+/// ---------------------------------
+/// |   x   | inv | out |     1     |
+/// ```
+#[derive(Debug, Clone)]
+pub struct IsZeroConfig<F: FieldExt> {
+    pub value: Column<Advice>,
+    inv: Column<Advice>,
+    out: Column<Advice>,
+    q_is_zero: Selector,
+    _marker: PhantomData<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IsZeroChip<F: FieldExt> {
+    config: IsZeroConfig<F>,
+}
+
+impl<F: FieldExt> IsZeroChip<F> {
+    pub fn construct(config: IsZeroConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, value: Column<Advice>) -> IsZeroConfig<F> {
+        let inv = meta.advice_column();
+        let out = meta.advice_column();
+        meta.enable_equality(value);
+        meta.enable_equality(out);
+
+        let q_is_zero = meta.selector();
+
+        meta.create_gate("is zero", |meta| {
+            let q_is_zero = meta.query_selector(q_is_zero);
+
+            let x = meta.query_advice(value, Rotation::cur());
+            let inv = meta.query_advice(inv, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+
+            let one = Expression::Constant(F::one());
+
+            Constraints::with_selector(
+                q_is_zero,
+                [
+                    ("out = 1 - x * inv", out.clone() - (one - x.clone() * inv)),
+                    ("x * out = 0", x * out),
+                ],
+            )
+        });
+
+        IsZeroConfig { value, inv, out, q_is_zero, _marker: PhantomData }
+    }
+
+    /// Lays out the check of `x` with the given `inv` and `out` witnesses.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        inv: Value<F>,
+        out: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(
+            || "is zero",
+            |mut region| {
+                config.q_is_zero.enable(&mut region, 0)?;
+
+                x.copy_advice(|| "x", &mut region, config.value, 0)?;
+                region.assign_advice(|| "inv", config.inv, 0, || inv)?;
+                region.assign_advice(|| "out", config.out, 0, || out)
+            },
+        )
+    }
+}
+
+impl<F: FieldExt> IsZeroInstructions<F> for IsZeroChip<F> {
+    fn is_zero(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        // 0 has no inverse, and 0 is as good as any other witness there
+        let inv = x.value().map(|x| x.invert().unwrap_or_else(F::zero));
+        let out = x.value().map(|x| if *x == F::zero() { F::one() } else { F::zero() });
+
+        self.assign(layouter, x, inv, out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{
+        circuit::floor_planner::V1,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::dev::{assert_witness_sound, find_unconstrained_cells};
+
+    /// Exposes is_zero(x), with the inverse and output witnesses overridden if set
+    #[derive(Clone, Default)]
+    struct MyCircuit {
+        x: Value<Fp>,
+        malicious: Option<(Fp, Fp)>,
+    }
+
+    impl Circuit<Fp> for MyCircuit {
+        type Config = (IsZeroConfig<Fp>, Column<Instance>);
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let value = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            (IsZeroChip::configure(meta, value), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = IsZeroChip::construct(config.clone());
+
+            let x = layouter.assign_region(
+                || "Witness x",
+                |mut region| region.assign_advice(|| "x", config.value, 0, || self.x),
+            )?;
+
+            let out = match self.malicious {
+                None => chip.is_zero(layouter.namespace(|| "is zero"), &x)?,
+                Some((inv, out)) => chip.assign(
+                    layouter.namespace(|| "is zero"),
+                    &x,
+                    Value::known(inv),
+                    Value::known(out),
+                )?,
+            };
+
+            layouter.constrain_instance(out.cell(), instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_is_zero() {
+        let k = 4;
+
+        // IsZero with "in": "0"
+        for (x, out) in
+            [(Fp::zero(), Fp::one()), (Fp::from(2), Fp::zero()), (-Fp::one(), Fp::zero())]
+        {
+            let circuit = MyCircuit { x: Value::known(x), malicious: None };
+
+            let prover = MockProver::run(k, &circuit, vec![vec![out]]).unwrap();
+            prover.assert_satisfied();
+
+            let prover = MockProver::run(k, &circuit, vec![vec![Fp::one() - out]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_is_zero_malicious_prover() {
+        let k = 4;
+        let x = Fp::from(5);
+        let inv = x.invert().unwrap();
+
+        // claims 0 is not zero, or that 5 is
+        let cases = [
+            (Fp::zero(), [(Fp::zero(), Fp::zero()), (Fp::one(), Fp::zero()), (inv, Fp::zero())]),
+            (x, [(Fp::zero(), Fp::one()), (inv, Fp::one()), (Fp::one(), Fp::one())]),
+        ];
+
+        for (x, witnesses) in cases {
+            for (inv, out) in witnesses {
+                let circuit = MyCircuit { x: Value::known(x), malicious: Some((inv, out)) };
+                let prover = MockProver::run(k, &circuit, vec![vec![out]]).unwrap();
+                assert!(prover.verify().is_err());
+            }
+        }
+
+        // nor can out = 0 stand with a wrong inverse, as 1 - 5 * 2 ≠ 0
+        let circuit = MyCircuit { x: Value::known(x), malicious: Some((Fp::from(2), Fp::zero())) };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::zero()]]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_is_zero_witness_sound() {
+        let circuit = MyCircuit { x: Value::known(Fp::from(7)), malicious: None };
+        assert_witness_sound(4, circuit, vec![vec![Fp::zero()]]);
+
+        // 0 * inv = 0 whatever inv is, but out stays pinned to 1
+        let circuit = MyCircuit { x: Value::known(Fp::zero()), malicious: None };
+        let unconstrained = find_unconstrained_cells(4, circuit, vec![vec![Fp::one()]]);
+        let annotations: Vec<_> =
+            unconstrained.iter().map(|cell| cell.annotation.as_str()).collect();
+        assert_eq!(annotations, ["inv"]);
+    }
+}
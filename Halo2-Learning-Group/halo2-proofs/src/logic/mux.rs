@@ -0,0 +1,532 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+
+use super::is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstructions};
+
+/// What `select` does with an index outside of [0, n).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutOfRange {
+    /// The circuit is unsatisfiable
+    Fail,
+    /// The output is 0, as in `Selector.circom`
+    ReturnZero,
+}
+
+/// Array access at a witnessed index, as the circom `Selector` template but with the
+/// choice of the element constrained.
+pub trait MuxInstructions<F: FieldExt> {
+    /// Returns inputs[index], handling an out-of-range index as `policy` says.
+    fn select(
+        &self,
+        layouter: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>],
+        index: &AssignedCell<F, F>,
+        policy: OutOfRange,
+    ) -> Result<AssignedCell<F, F>, Error>;
+}
+
+/// Each input gets an indicator b_i, and the output accumulates Σ b_i in_i along with
+/// Σ b_i and Σ i b_i:
+///
+/// ```text
+/// | input | index |  b  |  sum  |   idx    |     out      | pos |
+/// ---------------------------------------------------------------
+/// | in_0  | index | b_0 |  b_0  |    0     |  b_0 in_0    |  0  |
+/// | in_1  | index | b_1 | + b_1 | + 1 b_1  | + b_1 in_1   |  1  |
+/// |  ...  |  ...  | ... |  ...  |   ...    |     ...      | ... |
+/// | in_n-1| index |b_n-1|   1   |  index   |     out      | n-1 |
+/// ```
+///
+/// With `OutOfRange::Fail` the indicators are boolean, and the last row enforces
+/// Σ b_i = 1 and Σ i b_i = index, so they are the one-hot encoding of index, which has to
+/// be in range.
+///
+/// With `OutOfRange::ReturnZero` every indicator is copied from b_i = is_zero(index - i),
+/// computed by `IsZeroChip`, so they are all 0 for an out-of-range index and the output is
+/// 0. The differences are laid out in a region of their own beforehand:
+///
+/// ```text
+/// | index |     diff      | pos | q_diff |
+/// ----------------------------------------
+/// | index |   index - 0   |  0  |   1    |
+/// |  ...  |      ...      | ... |  ...   |
+/// | index | index - (n-1) | n-1 |   1    |
+/// ```
+#[derive(Debug, Clone)]
+pub struct MuxConfig<F: FieldExt> {
+    pub input: Column<Advice>,
+    pub index: Column<Advice>,
+    indicator: Column<Advice>,
+    diff: Column<Advice>,
+    sum: Column<Advice>,
+    idx: Column<Advice>,
+    out: Column<Advice>,
+    pos: Column<Fixed>,
+    q_bit: Selector,
+    q_diff: Selector,
+    q_first: Selector,
+    q_step: Selector,
+    q_in_bounds: Selector,
+    is_zero: IsZeroConfig<F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MuxChip<F: FieldExt> {
+    config: MuxConfig<F>,
+}
+
+impl<F: FieldExt> MuxChip<F> {
+    pub fn construct(config: MuxConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        input: Column<Advice>,
+        index: Column<Advice>,
+    ) -> MuxConfig<F> {
+        let indicator = meta.advice_column();
+        let diff = meta.advice_column();
+        let sum = meta.advice_column();
+        let idx = meta.advice_column();
+        let out = meta.advice_column();
+        for column in [input, index, indicator, out] {
+            meta.enable_equality(column);
+        }
+
+        // enables equality on `diff`
+        let is_zero = IsZeroChip::configure(meta, diff);
+
+        let pos = meta.fixed_column();
+
+        let q_bit = meta.selector();
+        let q_diff = meta.selector();
+        let q_first = meta.selector();
+        let q_step = meta.selector();
+        let q_in_bounds = meta.selector();
+
+        meta.create_gate("boolean indicator", |meta| {
+            let q_bit = meta.query_selector(q_bit);
+            let b = meta.query_advice(indicator, Rotation::cur());
+
+            let one = Expression::Constant(F::one());
+
+            vec![q_bit * b.clone() * (one - b)]
+        });
+
+        meta.create_gate("index - i", |meta| {
+            let q_diff = meta.query_selector(q_diff);
+
+            let index = meta.query_advice(index, Rotation::cur());
+            let pos = meta.query_fixed(pos, Rotation::cur());
+            let diff = meta.query_advice(diff, Rotation::cur());
+
+            vec![q_diff * (diff - (index - pos))]
+        });
+
+        meta.create_gate("first accumulation", |meta| {
+            let q_first = meta.query_selector(q_first);
+
+            let input = meta.query_advice(input, Rotation::cur());
+            let b = meta.query_advice(indicator, Rotation::cur());
+            let pos = meta.query_fixed(pos, Rotation::cur());
+            let sum = meta.query_advice(sum, Rotation::cur());
+            let idx = meta.query_advice(idx, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+
+            Constraints::with_selector(
+                q_first,
+                [
+                    ("sum", sum - b.clone()),
+                    ("idx", idx - pos * b.clone()),
+                    ("out", out - b * input),
+                ],
+            )
+        });
+
+        // separate from the first row, which has no previous accumulators
+        meta.create_gate("accumulation", |meta| {
+            let q_step = meta.query_selector(q_step);
+
+            let input = meta.query_advice(input, Rotation::cur());
+            let b = meta.query_advice(indicator, Rotation::cur());
+            let pos = meta.query_fixed(pos, Rotation::cur());
+            let sum_cur = meta.query_advice(sum, Rotation::cur());
+            let sum_prev = meta.query_advice(sum, Rotation::prev());
+            let idx_cur = meta.query_advice(idx, Rotation::cur());
+            let idx_prev = meta.query_advice(idx, Rotation::prev());
+            let out_cur = meta.query_advice(out, Rotation::cur());
+            let out_prev = meta.query_advice(out, Rotation::prev());
+
+            Constraints::with_selector(
+                q_step,
+                [
+                    ("sum", sum_cur - sum_prev - b.clone()),
+                    ("idx", idx_cur - idx_prev - pos * b.clone()),
+                    ("out", out_cur - out_prev - b * input),
+                ],
+            )
+        });
+
+        meta.create_gate("in bounds", |meta| {
+            let q_in_bounds = meta.query_selector(q_in_bounds);
+
+            let index = meta.query_advice(index, Rotation::cur());
+            let sum = meta.query_advice(sum, Rotation::cur());
+            let idx = meta.query_advice(idx, Rotation::cur());
+
+            let one = Expression::Constant(F::one());
+
+            Constraints::with_selector(
+                q_in_bounds,
+                [("Σ b_i = 1", sum - one), ("Σ i b_i = index", idx - index)],
+            )
+        });
+
+        MuxConfig {
+            input,
+            index,
+            indicator,
+            diff,
+            sum,
+            idx,
+            out,
+            pos,
+            q_bit,
+            q_diff,
+            q_first,
+            q_step,
+            q_in_bounds,
+            is_zero,
+        }
+    }
+
+    /// Returns is_zero(index - i) for every position i < n.
+    fn indicators_of(
+        &self,
+        mut layouter: impl Layouter<F>,
+        index: &AssignedCell<F, F>,
+        n: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let config = &self.config;
+
+        let diffs = layouter.assign_region(
+            || "index - i",
+            |mut region| {
+                (0..n)
+                    .map(|offset| {
+                        let pos = F::from(offset as u64);
+                        config.q_diff.enable(&mut region, offset)?;
+
+                        index.copy_advice(|| "index", &mut region, config.index, offset)?;
+                        region.assign_fixed(|| "i", config.pos, offset, || Value::known(pos))?;
+
+                        let diff = index.value().map(|index| *index - pos);
+                        region.assign_advice(|| "index - i", config.diff, offset, || diff)
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )?;
+
+        let is_zero = IsZeroChip::construct(config.is_zero.clone());
+        diffs
+            .iter()
+            .enumerate()
+            .map(|(i, diff)| is_zero.is_zero(layouter.namespace(|| format!("b_{}", i)), diff))
+            .collect()
+    }
+
+    /// Lays out the selection with the given indicators, which are tied to the is-zero checks
+    /// of the index under `OutOfRange::ReturnZero`.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>],
+        index: &AssignedCell<F, F>,
+        policy: OutOfRange,
+        indicators: &[Value<F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        assert!(!inputs.is_empty(), "at least one input is needed");
+        assert_eq!(inputs.len(), indicators.len());
+
+        let is_zero = match policy {
+            OutOfRange::Fail => None,
+            OutOfRange::ReturnZero => Some(self.indicators_of(
+                layouter.namespace(|| "indicators"),
+                index,
+                inputs.len(),
+            )?),
+        };
+
+        layouter.assign_region(
+            || "select",
+            |mut region| {
+                let mut sum = Value::known(F::zero());
+                let mut idx = Value::known(F::zero());
+                let mut out: Option<AssignedCell<F, F>> = None;
+
+                for (offset, (input, b)) in inputs.iter().zip(indicators.iter()).enumerate() {
+                    let pos = F::from(offset as u64);
+
+                    let input = input.copy_advice(|| "input", &mut region, config.input, offset)?;
+                    index.copy_advice(|| "index", &mut region, config.index, offset)?;
+                    let b_cell = region.assign_advice(|| "b", config.indicator, offset, || *b)?;
+
+                    match &is_zero {
+                        None => config.q_bit.enable(&mut region, offset)?,
+                        Some(is_zero) => {
+                            region.constrain_equal(b_cell.cell(), is_zero[offset].cell())?
+                        }
+                    }
+                    region.assign_fixed(|| "i", config.pos, offset, || Value::known(pos))?;
+
+                    let term = b.zip(input.value()).map(|(b, input)| b * *input);
+                    let acc = match &out {
+                        None => {
+                            config.q_first.enable(&mut region, offset)?;
+                            term
+                        }
+                        Some(out) => {
+                            config.q_step.enable(&mut region, offset)?;
+                            out.value().copied() + term
+                        }
+                    };
+
+                    sum = sum + *b;
+                    idx = idx + b.map(|b| b * pos);
+                    region.assign_advice(|| "sum", config.sum, offset, || sum)?;
+                    region.assign_advice(|| "idx", config.idx, offset, || idx)?;
+                    out = Some(region.assign_advice(|| "out", config.out, offset, || acc)?);
+                }
+
+                if policy == OutOfRange::Fail {
+                    config.q_in_bounds.enable(&mut region, inputs.len() - 1)?;
+                }
+
+                Ok(out.unwrap())
+            },
+        )
+    }
+}
+
+impl<F: FieldExt> MuxInstructions<F> for MuxChip<F> {
+    fn select(
+        &self,
+        layouter: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>],
+        index: &AssignedCell<F, F>,
+        policy: OutOfRange,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let indicators: Vec<Value<F>> =
+            (0..inputs.len())
+                .map(|i| {
+                    index.value().map(|index| {
+                        if *index == F::from(i as u64) {
+                            F::one()
+                        } else {
+                            F::zero()
+                        }
+                    })
+                })
+                .collect();
+
+        self.assign(layouter, inputs, index, policy, &indicators)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::floor_planner::V1,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::dev::{assert_witness_sound, find_unconstrained_cells};
+
+    /// Selects among private inputs at a private index and exposes the result, with the
+    /// indicators overridden if set
+    #[derive(Clone)]
+    struct MyCircuit {
+        inputs: Vec<Value<Fp>>,
+        index: Value<Fp>,
+        policy: OutOfRange,
+        malicious: Option<Vec<Fp>>,
+    }
+
+    impl Circuit<Fp> for MyCircuit {
+        type Config = (MuxConfig<Fp>, Column<Instance>);
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                inputs: vec![Value::unknown(); self.inputs.len()],
+                index: Value::unknown(),
+                policy: self.policy,
+                malicious: None,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let input = meta.advice_column();
+            let index = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            (MuxChip::configure(meta, input, index), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = MuxChip::construct(config.clone());
+
+            let (inputs, index) = layouter.assign_region(
+                || "Witness inputs",
+                |mut region| {
+                    let inputs = self
+                        .inputs
+                        .iter()
+                        .enumerate()
+                        .map(|(row, value)| {
+                            region.assign_advice(|| "in", config.input, row, || *value)
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let index = region.assign_advice(|| "index", config.index, 0, || self.index)?;
+                    Ok((inputs, index))
+                },
+            )?;
+
+            let namespace = layouter.namespace(|| "select");
+            let out = match &self.malicious {
+                None => chip.select(namespace, &inputs, &index, self.policy)?,
+                Some(indicators) => {
+                    let indicators: Vec<_> = indicators.iter().copied().map(Value::known).collect();
+                    chip.assign(namespace, &inputs, &index, self.policy, &indicators)?
+                }
+            };
+
+            layouter.constrain_instance(out.cell(), instance, 0)
+        }
+    }
+
+    fn honest(inputs: &[u64], index: Fp, policy: OutOfRange) -> MyCircuit {
+        MyCircuit {
+            inputs: inputs.iter().map(|v| Value::known(Fp::from(*v))).collect(),
+            index: Value::known(index),
+            policy,
+            malicious: None,
+        }
+    }
+
+    fn one_hot(n: usize, i: usize) -> Vec<Fp> {
+        (0..n).map(|j| Fp::from((i == j) as u64)).collect()
+    }
+
+    fn verify(circuit: &MyCircuit, out: Fp) -> Result<(), ()> {
+        let prover = MockProver::run(5, circuit, vec![vec![out]]).unwrap();
+        prover.verify().map_err(|_| ())
+    }
+
+    const INPUTS: [u64; 5] = [10, 11, 12, 13, 14];
+    const POLICIES: [OutOfRange; 2] = [OutOfRange::Fail, OutOfRange::ReturnZero];
+
+    #[test]
+    fn test_selector() {
+        // Selector(2) with "in": ["0", "2"], "index": "1"
+        for policy in POLICIES {
+            let circuit = honest(&[0, 2], Fp::one(), policy);
+            assert_eq!(verify(&circuit, Fp::from(2)), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_mux_every_index() {
+        for policy in POLICIES {
+            for (i, value) in INPUTS.iter().enumerate() {
+                let circuit = honest(&INPUTS, Fp::from(i as u64), policy);
+                assert_eq!(verify(&circuit, Fp::from(*value)), Ok(()));
+                assert!(verify(&circuit, Fp::from(*value + 1)).is_err());
+            }
+        }
+
+        // a single input
+        let circuit = honest(&[7], Fp::zero(), OutOfRange::Fail);
+        assert_eq!(verify(&circuit, Fp::from(7)), Ok(()));
+    }
+
+    #[test]
+    fn test_mux_out_of_range() {
+        for index in [Fp::from(INPUTS.len() as u64), Fp::from(100), -Fp::one()] {
+            let circuit = honest(&INPUTS, index, OutOfRange::Fail);
+            assert!(verify(&circuit, Fp::zero()).is_err());
+
+            let circuit = honest(&INPUTS, index, OutOfRange::ReturnZero);
+            assert_eq!(verify(&circuit, Fp::zero()), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_mux_malicious_prover() {
+        let n = INPUTS.len();
+        let with_indicators = |index: u64, policy, indicators: Vec<Fp>| MyCircuit {
+            malicious: Some(indicators),
+            ..honest(&INPUTS, Fp::from(index), policy)
+        };
+
+        for policy in POLICIES {
+            // in[2] at index 1
+            let circuit = with_indicators(1, policy, one_hot(n, 2));
+            assert!(verify(&circuit, Fp::from(INPUTS[2])).is_err());
+
+            // in[4] for the out-of-range index 5
+            let circuit = with_indicators(n as u64, policy, one_hot(n, n - 1));
+            assert!(verify(&circuit, Fp::from(INPUTS[n - 1])).is_err());
+
+            // in[1] + in[3] at index 4 = 1 + 3
+            let mut indicators = one_hot(n, 1);
+            indicators[3] = Fp::one();
+            let circuit = with_indicators(4, policy, indicators);
+            assert!(verify(&circuit, Fp::from(INPUTS[1] + INPUTS[3])).is_err());
+        }
+
+        // 0 rather than in[1] at the in-range index 1
+        let circuit = with_indicators(1, OutOfRange::ReturnZero, vec![Fp::zero(); n]);
+        assert!(verify(&circuit, Fp::zero()).is_err());
+
+        // 2 in[0] - in[1] at index 0 - 1 = -1, indicators summing to 1
+        let mut indicators = one_hot(n, 0);
+        indicators[0] = Fp::from(2);
+        indicators[1] = -Fp::one();
+        let mut circuit = with_indicators(0, OutOfRange::Fail, indicators);
+        circuit.index = Value::known(-Fp::one());
+        assert!(verify(&circuit, Fp::from(2 * INPUTS[0]) - Fp::from(INPUTS[1])).is_err());
+    }
+
+    #[test]
+    fn test_mux_witness_sound() {
+        let circuit = honest(&INPUTS, Fp::from(3), OutOfRange::Fail);
+        assert_witness_sound(5, circuit, vec![vec![Fp::from(INPUTS[3])]]);
+
+        let circuit = honest(&INPUTS, Fp::from(9), OutOfRange::ReturnZero);
+        assert_witness_sound(5, circuit, vec![vec![Fp::zero()]]);
+
+        // the inverse of `IsZeroChip` is free where index - i = 0, only for b_3
+        let circuit = honest(&INPUTS, Fp::from(3), OutOfRange::ReturnZero);
+        let unconstrained = find_unconstrained_cells(5, circuit, vec![vec![Fp::from(INPUTS[3])]]);
+        let cells: Vec<_> = unconstrained
+            .iter()
+            .map(|cell| (cell.region.as_str(), cell.annotation.as_str()))
+            .collect();
+        assert_eq!(cells, [("is zero", "inv")]);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod chip;
+pub mod tree;
+
+pub use chip::{MerkleChip, MerkleConfig, MerkleInstructions};
+pub use tree::{MerklePath, MerkleTree, PoseidonHasher};
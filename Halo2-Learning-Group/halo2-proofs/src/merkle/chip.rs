@@ -0,0 +1,314 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use crate::poseidon::{chip::hash, P128Pow5T3, Pow5Chip, Pow5Config};
+
+/// Recomputes a Merkle root, as the circom `MerkleTreeMembership` template.
+pub trait MerkleInstructions<F: FieldExt> {
+    /// The root reached from `leaf` through `siblings`, path index i being 1 if sibling i
+    /// is on the left.
+    fn calculate_root(
+        &self,
+        layouter: impl Layouter<F>,
+        leaf: &AssignedCell<F, F>,
+        siblings: &[Value<F>],
+        path_indices: &[Value<F>],
+    ) -> Result<AssignedCell<F, F>, Error>;
+}
+
+/// Every level orders the current node and its sibling as the circom `DualMax` template,
+/// on the columns of the Poseidon state, then hashes the pair with `Pow5Chip`:
+///
+/// ```text
+/// |  state_0  |  state_1  | state_2 | q_swap |
+/// --------------------------------------------
+/// |   node    |  sibling  |  index  |   1    |
+/// |   left    |   right   |         |   0    |
+/// ```
+///
+/// ```text
+/// index ∈ {0, 1}
+/// left  = node + index * (sibling - node)
+/// right = sibling + index * (node - sibling)
+/// ```
+#[derive(Debug, Clone)]
+pub struct MerkleConfig<F: FieldExt> {
+    pub(crate) poseidon: Pow5Config<F, 3, 2>,
+    q_swap: Selector,
+}
+
+/// The inputs of the hash of a level
+struct Pair<F: FieldExt> {
+    left: AssignedCell<F, F>,
+    right: AssignedCell<F, F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MerkleChip<F: FieldExt> {
+    config: MerkleConfig<F>,
+}
+
+impl<F: FieldExt> MerkleChip<F> {
+    pub fn construct(config: MerkleConfig<F>) -> Self {
+        Self { config }
+    }
+
+    /// A constants column must be enabled for the hashes.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        state: [Column<Advice>; 3],
+    ) -> MerkleConfig<F> {
+        let poseidon = Pow5Chip::configure::<P128Pow5T3>(meta, state);
+
+        let q_swap = meta.selector();
+
+        meta.create_gate("swap", |meta| {
+            let q_swap = meta.query_selector(q_swap);
+
+            let node = meta.query_advice(state[0], Rotation::cur());
+            let sibling = meta.query_advice(state[1], Rotation::cur());
+            let index = meta.query_advice(state[2], Rotation::cur());
+            let left = meta.query_advice(state[0], Rotation::next());
+            let right = meta.query_advice(state[1], Rotation::next());
+
+            let one = Expression::Constant(F::one());
+
+            Constraints::with_selector(
+                q_swap,
+                [
+                    ("boolean index", index.clone() * (one - index.clone())),
+                    (
+                        "left",
+                        left - (node.clone() + index.clone() * (sibling.clone() - node.clone())),
+                    ),
+                    ("right", right - (sibling.clone() + index * (node - sibling))),
+                ],
+            )
+        });
+
+        MerkleConfig { poseidon, q_swap }
+    }
+
+    /// Orders the node and its sibling as the inputs of the hash of a level.
+    fn swap(
+        &self,
+        mut layouter: impl Layouter<F>,
+        node: &AssignedCell<F, F>,
+        sibling: Value<F>,
+        index: Value<F>,
+    ) -> Result<Pair<F>, Error> {
+        let config = &self.config;
+        let state = config.poseidon.state;
+
+        layouter.assign_region(
+            || "swap",
+            |mut region| {
+                config.q_swap.enable(&mut region, 0)?;
+
+                node.copy_advice(|| "node", &mut region, state[0], 0)?;
+                region.assign_advice(|| "sibling", state[1], 0, || sibling)?;
+                region.assign_advice(|| "index", state[2], 0, || index)?;
+
+                let left = node
+                    .value()
+                    .zip(sibling)
+                    .zip(index)
+                    .map(|((node, sibling), index)| *node + index * (sibling - node));
+                let right = node
+                    .value()
+                    .zip(sibling)
+                    .zip(index)
+                    .map(|((node, sibling), index)| sibling + index * (*node - sibling));
+
+                let left = region.assign_advice(|| "left", state[0], 1, || left)?;
+                let right = region.assign_advice(|| "right", state[1], 1, || right)?;
+                Ok(Pair { left, right })
+            },
+        )
+    }
+}
+
+impl<F: FieldExt> MerkleInstructions<F> for MerkleChip<F> {
+    fn calculate_root(
+        &self,
+        mut layouter: impl Layouter<F>,
+        leaf: &AssignedCell<F, F>,
+        siblings: &[Value<F>],
+        path_indices: &[Value<F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert_eq!(siblings.len(), path_indices.len());
+        let poseidon = Pow5Chip::construct(self.config.poseidon.clone());
+
+        let mut node = leaf.clone();
+        for (level, (sibling, index)) in siblings.iter().zip(path_indices.iter()).enumerate() {
+            let Pair { left, right } = self.swap(
+                layouter.namespace(|| format!("swap {}", level)),
+                &node,
+                *sibling,
+                *index,
+            )?;
+            node = hash(
+                poseidon.clone(),
+                layouter.namespace(|| format!("hash {}", level)),
+                &[left, right],
+            )?;
+        }
+
+        Ok(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::floor_planner::V1,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::{
+        dev::assert_witness_sound,
+        merkle::tree::{MerklePath, MerkleTree},
+    };
+
+    /// Proves a private leaf is under the public root
+    #[derive(Clone)]
+    struct MyCircuit {
+        leaf: Value<Fp>,
+        siblings: Vec<Value<Fp>>,
+        path_indices: Vec<Value<Fp>>,
+    }
+
+    impl MyCircuit {
+        fn new(leaf: Fp, path: &MerklePath<Fp>) -> Self {
+            Self {
+                leaf: Value::known(leaf),
+                siblings: path.siblings.iter().map(|s| Value::known(*s)).collect(),
+                path_indices: path
+                    .path_indices
+                    .iter()
+                    .map(|left| Value::known(Fp::from(*left as u64)))
+                    .collect(),
+            }
+        }
+    }
+
+    impl Circuit<Fp> for MyCircuit {
+        type Config = (MerkleConfig<Fp>, Column<Advice>, Column<Instance>);
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                leaf: Value::unknown(),
+                siblings: vec![Value::unknown(); self.siblings.len()],
+                path_indices: vec![Value::unknown(); self.path_indices.len()],
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            let state = [(); 3].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            (MerkleChip::configure(meta, state), state[0], instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, advice, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let leaf = layouter.assign_region(
+                || "Witness leaf",
+                |mut region| region.assign_advice(|| "leaf", advice, 0, || self.leaf),
+            )?;
+
+            let chip = MerkleChip::construct(config);
+            let root = chip.calculate_root(
+                layouter.namespace(|| "merkle"),
+                &leaf,
+                &self.siblings,
+                &self.path_indices,
+            )?;
+
+            layouter.constrain_instance(root.cell(), instance, 0)
+        }
+    }
+
+    fn verify(k: u32, circuit: &MyCircuit, root: Fp) -> Result<(), ()> {
+        let prover = MockProver::run(k, circuit, vec![vec![root]]).unwrap();
+        prover.verify().map_err(|_| ())
+    }
+
+    #[test]
+    fn test_merkle_chip() {
+        // each level takes 70 rows
+        for (depth, k) in [(1, 8), (5, 9), (32, 12)] {
+            let mut tree = MerkleTree::new(depth);
+            let last = (1u64 << depth) - 1;
+            for index in [0, last / 2, last] {
+                tree.insert(index, Fp::from(index + 100));
+            }
+
+            for index in [0, last / 2, last] {
+                let path = tree.path(index);
+                let circuit = MyCircuit::new(Fp::from(index + 100), &path);
+                assert_eq!(verify(k, &circuit, tree.root()), Ok(()));
+                assert!(verify(k, &circuit, tree.root() + Fp::one()).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_merkle_chip_malicious_prover() {
+        let k = 9;
+        let mut tree = MerkleTree::new(4);
+        tree.insert(5, Fp::from(42));
+        tree.insert(6, Fp::from(43));
+        let path = tree.path(5);
+
+        // a leaf not in the tree
+        let circuit = MyCircuit::new(Fp::from(41), &path);
+        assert!(verify(k, &circuit, tree.root()).is_err());
+
+        // a wrong sibling
+        let mut wrong = path.clone();
+        wrong.siblings[2] += Fp::one();
+        let circuit = MyCircuit::new(Fp::from(42), &wrong);
+        assert!(verify(k, &circuit, tree.root()).is_err());
+
+        // a flipped path index
+        let mut wrong = path.clone();
+        wrong.path_indices[0] = !wrong.path_indices[0];
+        let circuit = MyCircuit::new(Fp::from(42), &wrong);
+        assert!(verify(k, &circuit, tree.root()).is_err());
+
+        // a non-boolean index, with the root the swap formulas lead to
+        let (leaf, sibling, index) = (Fp::from(42), Fp::from(7), Fp::from(2));
+        let left = leaf + index * (sibling - leaf);
+        let right = sibling + index * (leaf - sibling);
+        let circuit = MyCircuit {
+            leaf: Value::known(leaf),
+            siblings: vec![Value::known(sibling)],
+            path_indices: vec![Value::known(index)],
+        };
+        assert!(verify(8, &circuit, tree.hasher().hash(&[left, right])).is_err());
+    }
+
+    #[test]
+    fn test_merkle_witness_sound() {
+        let mut tree = MerkleTree::new(2);
+        tree.insert(2, Fp::from(42));
+        let circuit = MyCircuit::new(Fp::from(42), &tree.path(2));
+        assert_witness_sound(8, circuit, vec![vec![tree.root()]]);
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use halo2_proofs::arithmetic::FieldExt;
+
+use crate::poseidon::{Hasher, P128Pow5T3};
+
+/// The hash of the nodes, P128Pow5T3 over two elements
+pub type PoseidonHasher<F> = Hasher<F, P128Pow5T3, 3, 2>;
+
+/// The siblings from a leaf up to the root, with the side each of them is on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerklePath<F> {
+    pub siblings: Vec<F>,
+    /// true if the sibling is on the left, i.e. bit i of the leaf index
+    pub path_indices: Vec<bool>,
+}
+
+impl<F: FieldExt> MerklePath<F> {
+    /// The root the path leads to from `leaf`.
+    pub fn root(&self, hasher: &PoseidonHasher<F>, leaf: F) -> F {
+        self.siblings.iter().zip(self.path_indices.iter()).fold(leaf, |node, (sibling, left)| {
+            if *left {
+                hasher.hash(&[*sibling, node])
+            } else {
+                hasher.hash(&[node, *sibling])
+            }
+        })
+    }
+}
+
+/// A sparse Merkle tree of 2^depth leaves hashed in pairs with Poseidon, the absent leaves
+/// being 0.
+///
+/// Only the nodes above inserted leaves are stored, the others being the roots of empty
+/// subtrees:
+///
+/// ```text
+/// empty[0] = 0,    empty[i + 1] = H(empty[i], empty[i])
+/// ```
+#[derive(Debug, Clone)]
+pub struct MerkleTree<F: FieldExt> {
+    depth: usize,
+    hasher: PoseidonHasher<F>,
+    empty: Vec<F>,
+    /// The stored nodes of every level by index, the leaves first
+    nodes: Vec<HashMap<u64, F>>,
+}
+
+impl<F: FieldExt> MerkleTree<F> {
+    pub fn new(depth: usize) -> Self {
+        assert!((1..64).contains(&depth), "leaf indices are u64");
+
+        let hasher = PoseidonHasher::new();
+        let mut empty = vec![F::zero()];
+        for level in 0..depth {
+            empty.push(hasher.hash(&[empty[level], empty[level]]));
+        }
+
+        Self { depth, hasher, empty, nodes: vec![HashMap::new(); depth + 1] }
+    }
+
+    pub fn hasher(&self) -> &PoseidonHasher<F> {
+        &self.hasher
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn node(&self, level: usize, index: u64) -> F {
+        self.nodes[level].get(&index).copied().unwrap_or(self.empty[level])
+    }
+
+    pub fn root(&self) -> F {
+        self.node(self.depth, 0)
+    }
+
+    pub fn leaf(&self, index: u64) -> F {
+        self.node(0, index)
+    }
+
+    /// Sets the leaf at `index` and rehashes the nodes above it.
+    pub fn insert(&mut self, index: u64, leaf: F) {
+        assert!(index >> self.depth == 0, "index out of range");
+
+        let mut node = leaf;
+        let mut index = index;
+        self.nodes[0].insert(index, node);
+        for level in 0..self.depth {
+            let sibling = self.node(level, index ^ 1);
+            node = if index & 1 == 1 {
+                self.hasher.hash(&[sibling, node])
+            } else {
+                self.hasher.hash(&[node, sibling])
+            };
+            index >>= 1;
+            self.nodes[level + 1].insert(index, node);
+        }
+    }
+
+    /// The path from the leaf at `index`, which need not have been inserted.
+    pub fn path(&self, index: u64) -> MerklePath<F> {
+        assert!(index >> self.depth == 0, "index out of range");
+
+        let (siblings, path_indices) = (0..self.depth)
+            .map(|level| {
+                let index = index >> level;
+                (self.node(level, index ^ 1), index & 1 == 1)
+            })
+            .unzip();
+
+        MerklePath { siblings, path_indices }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::pasta::Fp;
+
+    use super::*;
+
+    #[test]
+    fn test_merkle_tree_dense() {
+        let depth = 3;
+        let leaves: Vec<Fp> = (0..1 << depth).map(|i| Fp::from(i * i + 1)).collect();
+
+        let mut tree = MerkleTree::new(depth);
+        for (index, leaf) in leaves.iter().enumerate() {
+            tree.insert(index as u64, *leaf);
+        }
+
+        // hashing every level in full
+        let hasher = PoseidonHasher::new();
+        let mut level = leaves.clone();
+        while level.len() > 1 {
+            level = level.chunks(2).map(|pair| hasher.hash(pair)).collect();
+        }
+        assert_eq!(tree.root(), level[0]);
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            let path = tree.path(index as u64);
+            assert_eq!(path.root(&hasher, *leaf), tree.root());
+            assert_eq!(tree.leaf(index as u64), *leaf);
+        }
+    }
+
+    #[test]
+    fn test_merkle_tree_depths() {
+        for depth in 1..=32 {
+            let mut tree = MerkleTree::new(depth);
+            let empty_root = tree.root();
+
+            let last = (1u64 << depth) - 1;
+            let indices = [0, last / 3, last];
+            for index in indices {
+                tree.insert(index, Fp::from(index + 7));
+            }
+            assert_ne!(tree.root(), empty_root);
+
+            for index in indices {
+                let path = tree.path(index);
+                assert_eq!(path.siblings.len(), depth);
+                assert_eq!(path.root(tree.hasher(), Fp::from(index + 7)), tree.root());
+                assert_ne!(path.root(tree.hasher(), Fp::from(index + 8)), tree.root());
+            }
+
+            // an absent leaf is 0
+            if depth > 2 {
+                assert_eq!(tree.path(1).root(tree.hasher(), Fp::zero()), tree.root());
+            }
+        }
+    }
+}
@@ -0,0 +1,8 @@
+pub mod chip;
+mod grain;
+mod mds;
+pub mod primitives;
+
+pub use chip::{PoseidonInstructions, Pow5Chip, Pow5Config};
+pub use mds::Mds;
+pub use primitives::{Hasher, P128Pow5T3, Spec};
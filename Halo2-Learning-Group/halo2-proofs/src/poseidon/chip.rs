@@ -0,0 +1,539 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+
+use super::{
+    mds::Mds,
+    primitives::{full_round, initial_capacity_element, is_full_round, partial_round, Spec},
+};
+
+/// The words of a Poseidon state
+pub type State<F, const WIDTH: usize> = [AssignedCell<F, F>; WIDTH];
+
+pub trait PoseidonInstructions<F: FieldExt, const WIDTH: usize, const RATE: usize> {
+    /// The state [0, .., 0, capacity, 0, ..] a sponge starts from.
+    fn initial_state(
+        &self,
+        layouter: impl Layouter<F>,
+        initial_capacity_element: F,
+    ) -> Result<State<F, WIDTH>, Error>;
+
+    /// Adds at most RATE inputs to the rate words of `state`, the missing ones being zero,
+    /// and applies the permutation.
+    fn absorb_and_permute(
+        &self,
+        layouter: impl Layouter<F>,
+        state: &State<F, WIDTH>,
+        inputs: &[AssignedCell<F, F>],
+    ) -> Result<State<F, WIDTH>, Error>;
+}
+
+/// One row per round, the state moving down the WIDTH advice columns:
+///
+/// ```text
+/// |  state_0 .. state_{WIDTH-1}   | rc_0 .. rc_{WIDTH-1} | q_absorb | q_full | q_partial |
+/// --------------------------------------------------------------------------------------
+/// |  previous state               |                      |    1     |   0    |     0     |
+/// |  inputs, 0 padded | (unused)  |                      |    0     |   0    |     0     |
+/// |  state after absorption       |     round 0 rcs      |    0     |   1    |     0     |
+/// |  ...                          |         ...          |    0     |  0/1   |    1/0    |
+/// |  state after round R - 1      |                      |    0     |   0    |     0     |
+/// ```
+///
+/// A full round is next_i = Σ_j mds_ij (s_j + rc_j)^5 and a partial one applies the S-box
+/// to the first word only, so every round gate has degree 6 with its selector.
+#[derive(Debug, Clone)]
+pub struct Pow5Config<F: FieldExt, const WIDTH: usize, const RATE: usize> {
+    pub state: [Column<Advice>; WIDTH],
+    round_constants: [Column<Fixed>; WIDTH],
+    q_absorb: Selector,
+    q_full: Selector,
+    q_partial: Selector,
+    full_rounds: usize,
+    partial_rounds: usize,
+    rcs: Vec<[F; WIDTH]>,
+    mds: Mds<F, WIDTH>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Pow5Chip<F: FieldExt, const WIDTH: usize, const RATE: usize> {
+    config: Pow5Config<F, WIDTH, RATE>,
+}
+
+impl<F: FieldExt, const WIDTH: usize, const RATE: usize> Pow5Chip<F, WIDTH, RATE> {
+    pub fn construct(config: Pow5Config<F, WIDTH, RATE>) -> Self {
+        Self { config }
+    }
+
+    /// A constants column must be enabled for the initial state and the padding.
+    pub fn configure<S: Spec<F, WIDTH, RATE>>(
+        meta: &mut ConstraintSystem<F>,
+        state: [Column<Advice>; WIDTH],
+    ) -> Pow5Config<F, WIDTH, RATE> {
+        for column in state {
+            meta.enable_equality(column);
+        }
+        let round_constants = [(); WIDTH].map(|_| meta.fixed_column());
+
+        let q_absorb = meta.selector();
+        let q_full = meta.selector();
+        let q_partial = meta.selector();
+
+        let (rcs, mds) = S::constants();
+
+        meta.create_gate("absorb", |meta| {
+            let q_absorb = meta.query_selector(q_absorb);
+
+            let constraints: Vec<_> = state
+                .iter()
+                .enumerate()
+                .map(|(i, column)| {
+                    let cur = meta.query_advice(*column, Rotation::cur());
+                    let absorbed = meta.query_advice(*column, Rotation(2));
+                    if i < RATE {
+                        let input = meta.query_advice(*column, Rotation::next());
+                        absorbed - (cur + input)
+                    } else {
+                        absorbed - cur
+                    }
+                })
+                .collect();
+
+            Constraints::with_selector(q_absorb, constraints)
+        });
+
+        let round_gate = |meta: &mut ConstraintSystem<F>, name, selector, full: bool| {
+            meta.create_gate(name, |meta| {
+                let selector = meta.query_selector(selector);
+
+                let words: Vec<Expression<F>> = state
+                    .iter()
+                    .zip(round_constants.iter())
+                    .enumerate()
+                    .map(|(i, (column, rc))| {
+                        let word = meta.query_advice(*column, Rotation::cur())
+                            + meta.query_fixed(*rc, Rotation::cur());
+                        if full || i == 0 {
+                            word.clone() * word.clone() * word.clone() * word.clone() * word
+                        } else {
+                            word
+                        }
+                    })
+                    .collect();
+
+                let constraints: Vec<_> = state
+                    .iter()
+                    .zip(mds.iter())
+                    .map(|(column, row)| {
+                        let next = meta.query_advice(*column, Rotation::next());
+                        let product = row
+                            .iter()
+                            .zip(words.iter())
+                            .fold(Expression::Constant(F::zero()), |acc, (m, word)| {
+                                acc + word.clone() * *m
+                            });
+                        next - product
+                    })
+                    .collect();
+
+                Constraints::with_selector(selector, constraints)
+            });
+        };
+        round_gate(meta, "full round", q_full, true);
+        round_gate(meta, "partial round", q_partial, false);
+
+        Pow5Config {
+            state,
+            round_constants,
+            q_absorb,
+            q_full,
+            q_partial,
+            full_rounds: S::full_rounds(),
+            partial_rounds: S::partial_rounds(),
+            rcs,
+            mds,
+        }
+    }
+
+    fn assign_state(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        state: Value<[F; WIDTH]>,
+    ) -> Result<State<F, WIDTH>, Error> {
+        let config = &self.config;
+
+        let mut cells = vec![];
+        for (i, column) in config.state.iter().enumerate() {
+            cells.push(region.assign_advice(
+                || "state",
+                *column,
+                offset,
+                || state.map(|s| s[i]),
+            )?);
+        }
+        Ok(cells.try_into().unwrap())
+    }
+}
+
+impl<F: FieldExt, const WIDTH: usize, const RATE: usize> PoseidonInstructions<F, WIDTH, RATE>
+    for Pow5Chip<F, WIDTH, RATE>
+{
+    fn initial_state(
+        &self,
+        mut layouter: impl Layouter<F>,
+        initial_capacity_element: F,
+    ) -> Result<State<F, WIDTH>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(
+            || "initial state",
+            |mut region| {
+                let mut cells = vec![];
+                for (i, column) in config.state.iter().enumerate() {
+                    let value = if i == RATE { initial_capacity_element } else { F::zero() };
+                    cells.push(region.assign_advice_from_constant(
+                        || "state",
+                        *column,
+                        0,
+                        value,
+                    )?);
+                }
+                Ok(cells.try_into().unwrap())
+            },
+        )
+    }
+
+    fn absorb_and_permute(
+        &self,
+        mut layouter: impl Layouter<F>,
+        state: &State<F, WIDTH>,
+        inputs: &[AssignedCell<F, F>],
+    ) -> Result<State<F, WIDTH>, Error> {
+        let config = &self.config;
+        assert!(inputs.len() <= RATE);
+
+        layouter.assign_region(
+            || "absorb and permute",
+            |mut region| {
+                config.q_absorb.enable(&mut region, 0)?;
+
+                for (word, column) in state.iter().zip(config.state.iter()) {
+                    word.copy_advice(|| "state", &mut region, *column, 0)?;
+                }
+
+                let mut block = vec![];
+                for (i, column) in config.state.iter().take(RATE).enumerate() {
+                    let input = match inputs.get(i) {
+                        Some(input) => input.copy_advice(|| "input", &mut region, *column, 1)?,
+                        None => region.assign_advice_from_constant(
+                            || "padding",
+                            *column,
+                            1,
+                            F::zero(),
+                        )?,
+                    };
+                    block.push(input.value().copied());
+                }
+
+                let mut values: Value<[F; WIDTH]> = state
+                    .iter()
+                    .map(|word| word.value().copied())
+                    .collect::<Value<Vec<F>>>()
+                    .map(|words| words.try_into().unwrap());
+                for (i, input) in block.into_iter().enumerate() {
+                    values = values.zip(input).map(|(mut s, input)| {
+                        s[i] += input;
+                        s
+                    });
+                }
+
+                let mut cells = self.assign_state(&mut region, 2, values)?;
+                for (round, rcs) in config.rcs.iter().enumerate() {
+                    let offset = 2 + round;
+
+                    for (rc, column) in rcs.iter().zip(config.round_constants.iter()) {
+                        region.assign_fixed(|| "rc", *column, offset, || Value::known(*rc))?;
+                    }
+
+                    let full = is_full_round(round, config.full_rounds, config.partial_rounds);
+                    if full {
+                        config.q_full.enable(&mut region, offset)?;
+                    } else {
+                        config.q_partial.enable(&mut region, offset)?;
+                    }
+
+                    values = values.map(|mut s| {
+                        if full {
+                            full_round(&mut s, rcs, &config.mds);
+                        } else {
+                            partial_round(&mut s, rcs, &config.mds);
+                        }
+                        s
+                    });
+                    cells = self.assign_state(&mut region, offset + 1, values)?;
+                }
+
+                Ok(cells)
+            },
+        )
+    }
+}
+
+/// A sponge in the circuit, constrained to output what `primitives::Sponge` computes.
+#[derive(Debug)]
+pub struct Sponge<F: FieldExt, const WIDTH: usize, const RATE: usize> {
+    chip: Pow5Chip<F, WIDTH, RATE>,
+    state: State<F, WIDTH>,
+    /// The inputs absorbed since the last permutation
+    block: Vec<AssignedCell<F, F>>,
+    /// The rate words not squeezed yet, in reverse order, once squeezing has started
+    squeezing: Option<Vec<AssignedCell<F, F>>>,
+}
+
+impl<F: FieldExt, const WIDTH: usize, const RATE: usize> Sponge<F, WIDTH, RATE> {
+    pub fn new(
+        chip: Pow5Chip<F, WIDTH, RATE>,
+        mut layouter: impl Layouter<F>,
+        initial_capacity_element: F,
+    ) -> Result<Self, Error> {
+        let state =
+            chip.initial_state(layouter.namespace(|| "initial state"), initial_capacity_element)?;
+        Ok(Self { chip, state, block: vec![], squeezing: None })
+    }
+
+    fn permute(&mut self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let block = std::mem::take(&mut self.block);
+        self.state =
+            self.chip.absorb_and_permute(layouter.namespace(|| "permute"), &self.state, &block)?;
+        Ok(())
+    }
+
+    /// Panics once squeezing has started.
+    pub fn absorb(
+        &mut self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        assert!(self.squeezing.is_none(), "cannot absorb after squeezing");
+
+        if self.block.len() == RATE {
+            self.permute(layouter)?;
+        }
+        self.block.push(value.clone());
+        Ok(())
+    }
+
+    pub fn squeeze(&mut self, layouter: impl Layouter<F>) -> Result<AssignedCell<F, F>, Error> {
+        let word = self.squeezing.as_mut().and_then(|words| words.pop());
+        if let Some(word) = word {
+            return Ok(word);
+        }
+
+        // the last block on the first squeeze, no input on the next ones
+        self.permute(layouter)?;
+        let mut words: Vec<_> = self.state[..RATE].iter().rev().cloned().collect();
+        let word = words.pop().unwrap();
+        self.squeezing = Some(words);
+        Ok(word)
+    }
+}
+
+/// Hashes `inputs` to one element as `primitives::hash`, with their length in the capacity.
+pub fn hash<F: FieldExt, const WIDTH: usize, const RATE: usize>(
+    chip: Pow5Chip<F, WIDTH, RATE>,
+    mut layouter: impl Layouter<F>,
+    inputs: &[AssignedCell<F, F>],
+) -> Result<AssignedCell<F, F>, Error> {
+    let mut sponge =
+        Sponge::new(chip, layouter.namespace(|| "init"), initial_capacity_element(inputs.len()))?;
+    for (i, input) in inputs.iter().enumerate() {
+        sponge.absorb(layouter.namespace(|| format!("absorb {}", i)), input)?;
+    }
+    sponge.squeeze(layouter.namespace(|| "squeeze"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use halo2_proofs::{
+        circuit::floor_planner::V1,
+        dev::MockProver,
+        pasta::{Fp, Fq},
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::{
+        dev::assert_witness_sound,
+        poseidon::primitives::{self, P128Pow5T3},
+    };
+
+    /// Hashes private inputs and exposes the digest, then `num_squeezed` outputs of a
+    /// sponge absorbing the same inputs
+    #[derive(Clone)]
+    struct MyCircuit<F: FieldExt, S, const WIDTH: usize, const RATE: usize> {
+        inputs: Vec<Value<F>>,
+        num_squeezed: usize,
+        _marker: PhantomData<S>,
+    }
+
+    impl<F: FieldExt, S: Spec<F, WIDTH, RATE>, const WIDTH: usize, const RATE: usize>
+        MyCircuit<F, S, WIDTH, RATE>
+    {
+        fn new(inputs: &[F], num_squeezed: usize) -> Self {
+            let inputs = inputs.iter().map(|input| Value::known(*input)).collect();
+            Self { inputs, num_squeezed, _marker: PhantomData }
+        }
+    }
+
+    impl<F: FieldExt, S: Spec<F, WIDTH, RATE> + Clone, const WIDTH: usize, const RATE: usize>
+        Circuit<F> for MyCircuit<F, S, WIDTH, RATE>
+    {
+        type Config = (Pow5Config<F, WIDTH, RATE>, Column<Instance>);
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self { inputs: vec![Value::unknown(); self.inputs.len()], ..self.clone() }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            let state = [(); WIDTH].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            (Pow5Chip::configure::<S>(meta, state), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            // a region per input, as an empty region trips up `MockProver` failure reports
+            let inputs = self
+                .inputs
+                .iter()
+                .map(|input| {
+                    layouter.assign_region(
+                        || "Witness input",
+                        |mut region| {
+                            region.assign_advice(|| "input", config.state[0], 0, || *input)
+                        },
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let chip = Pow5Chip::construct(config);
+
+            let digest = hash(chip.clone(), layouter.namespace(|| "hash"), &inputs)?;
+            layouter.constrain_instance(digest.cell(), instance, 0)?;
+
+            let mut sponge = Sponge::new(chip, layouter.namespace(|| "sponge"), F::zero())?;
+            for input in inputs.iter() {
+                sponge.absorb(layouter.namespace(|| "absorb"), input)?;
+            }
+            for row in 1..=self.num_squeezed {
+                let output = sponge.squeeze(layouter.namespace(|| "squeeze"))?;
+                layouter.constrain_instance(output.cell(), instance, row)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    fn public_input<F: FieldExt, S: Spec<F, WIDTH, RATE>, const WIDTH: usize, const RATE: usize>(
+        inputs: &[F],
+        num_squeezed: usize,
+    ) -> Vec<Vec<F>> {
+        let mut instance = vec![primitives::hash::<F, S, WIDTH, RATE>(inputs)];
+
+        let mut sponge = primitives::Sponge::<F, S, WIDTH, RATE>::new(F::zero());
+        for input in inputs {
+            sponge.absorb(*input);
+        }
+        instance.extend((0..num_squeezed).map(|_| sponge.squeeze()));
+
+        vec![instance]
+    }
+
+    /// A wider instance than `P128Pow5T3`, only to exercise the width and rate parameters
+    #[derive(Debug, Clone, Copy)]
+    struct Pow5T5;
+
+    impl<F: FieldExt> Spec<F, 5, 4> for Pow5T5 {
+        fn full_rounds() -> usize {
+            8
+        }
+
+        fn partial_rounds() -> usize {
+            60
+        }
+
+        fn secure_mds() -> usize {
+            0
+        }
+    }
+
+    #[test]
+    fn test_poseidon_chip() {
+        let k = 10;
+
+        for len in [0, 1, 2, 3, 5] {
+            let inputs: Vec<Fp> = (0..len as u64).map(Fp::from).collect();
+            let circuit = MyCircuit::<Fp, P128Pow5T3, 3, 2>::new(&inputs, 3);
+            let instance = public_input::<Fp, P128Pow5T3, 3, 2>(&inputs, 3);
+
+            let prover = MockProver::run(k, &circuit, instance.clone()).unwrap();
+            prover.assert_satisfied();
+
+            let mut wrong = instance;
+            wrong[0][0] += Fp::one();
+            let prover = MockProver::run(k, &circuit, wrong).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_poseidon_chip_orchard_vector() {
+        // from zcash-test-vectors orchard_poseidon/hash/fp.py, as in the native tests
+        let circuit = MyCircuit::<Fp, P128Pow5T3, 3, 2>::new(&[Fp::zero(), Fp::one()], 0);
+        let digest = primitives::hash::<Fp, P128Pow5T3, 3, 2>(&[Fp::zero(), Fp::one()]);
+        assert_eq!(
+            format!("{:?}", digest),
+            "0x062ff1c32bb0ef109d6a1bc9399a083eed83c2a7fb54cdbe389d32a011d75883"
+        );
+
+        let prover = MockProver::run(8, &circuit, vec![vec![digest]]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_poseidon_chip_vesta_and_width() {
+        let inputs: Vec<Fq> = (0..3).map(Fq::from).collect();
+        let circuit = MyCircuit::<Fq, P128Pow5T3, 3, 2>::new(&inputs, 1);
+        let instance = public_input::<Fq, P128Pow5T3, 3, 2>(&inputs, 1);
+        MockProver::run(9, &circuit, instance).unwrap().assert_satisfied();
+
+        let inputs: Vec<Fp> = (0..9).map(Fp::from).collect();
+        let circuit = MyCircuit::<Fp, Pow5T5, 5, 4>::new(&inputs, 5);
+        let instance = public_input::<Fp, Pow5T5, 5, 4>(&inputs, 5);
+        MockProver::run(10, &circuit, instance).unwrap().assert_satisfied();
+    }
+
+    #[test]
+    fn test_poseidon_witness_sound() {
+        // one permutation for the hash, none for the sponge which is not squeezed
+        let inputs = [Fp::from(3)];
+        let circuit = MyCircuit::<Fp, P128Pow5T3, 3, 2>::new(&inputs, 0);
+        let instance = public_input::<Fp, P128Pow5T3, 3, 2>(&inputs, 0);
+        assert_witness_sound(8, circuit, instance);
+    }
+}
@@ -0,0 +1,95 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::arithmetic::FieldExt;
+
+const STATE: usize = 80;
+
+/// The Grain LFSR in self-shrinking mode, which the Poseidon reference implementation uses
+/// to derive its round constants and MDS matrix.
+///
+/// The 80-bit state is seeded with the parameters of the instance:
+///
+/// ```text
+/// | field | sbox | field size | width | R_F | R_P  | 1...1 |
+/// |   2   |  4   |     12     |  12   | 10  |  10  |  30   |   bits
+/// ```
+///
+/// then clocked 160 times before the first output bit.
+pub(crate) struct Grain<F: FieldExt> {
+    state: [bool; STATE],
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Grain<F> {
+    /// A prime field with the x^alpha S-box.
+    pub(crate) fn new(width: usize, full_rounds: usize, partial_rounds: usize) -> Self {
+        let mut state = [true; STATE];
+        let mut offset = 0;
+        // the reference implementation writes each field most significant bit first
+        for (value, len) in [
+            (1, 2),
+            (0, 4),
+            (F::NUM_BITS as usize, 12),
+            (width, 12),
+            (full_rounds, 10),
+            (partial_rounds, 10),
+        ] {
+            for i in 0..len {
+                state[offset + i] = (value >> (len - 1 - i)) & 1 == 1;
+            }
+            offset += len;
+        }
+
+        let mut grain = Self { state, _marker: PhantomData };
+        for _ in 0..160 {
+            grain.clock();
+        }
+        grain
+    }
+
+    /// b_80 = b_62 + b_51 + b_38 + b_23 + b_13 + b_0
+    fn clock(&mut self) -> bool {
+        let bit = [62, 51, 38, 23, 13, 0].iter().fold(false, |acc, i| acc ^ self.state[*i]);
+        self.state.rotate_left(1);
+        self.state[STATE - 1] = bit;
+        bit
+    }
+
+    /// Of every pair of bits, outputs the second one if the first one is set.
+    fn next_bit(&mut self) -> bool {
+        while !self.clock() {
+            self.clock();
+        }
+        self.clock()
+    }
+
+    /// The next `F::NUM_BITS` bits, most significant first, as little-endian bytes.
+    fn next_bytes<const N: usize>(&mut self) -> [u8; N] {
+        let mut bytes = [0u8; N];
+        for i in (0..F::NUM_BITS as usize).rev() {
+            if self.next_bit() {
+                bytes[i / 8] |= 1 << (i % 8);
+            }
+        }
+        bytes
+    }
+
+    /// The next field element, rejecting the bit strings that are not canonical.
+    pub(crate) fn next_field_element(&mut self) -> F {
+        loop {
+            let bytes = self.next_bytes::<32>();
+            let mut repr = F::Repr::default();
+            repr.as_mut().copy_from_slice(&bytes);
+
+            if let Some(element) = Option::from(F::from_repr(repr)) {
+                return element;
+            }
+        }
+    }
+
+    /// The next field element, reducing the bit string modulo p, as the reference
+    /// implementation does for the MDS matrix.
+    pub(crate) fn next_field_element_without_rejection(&mut self) -> F {
+        F::from_bytes_wide(&self.next_bytes::<64>())
+    }
+}
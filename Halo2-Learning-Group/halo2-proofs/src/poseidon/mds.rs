@@ -0,0 +1,42 @@
+use halo2_proofs::arithmetic::FieldExt;
+
+use super::grain::Grain;
+
+/// A WIDTH x WIDTH matrix, row-major
+pub type Mds<F, const WIDTH: usize> = [[F; WIDTH]; WIDTH];
+
+/// The Cauchy matrix a_ij = 1 / (x_i + y_j) of the reference implementation, from 2 WIDTH
+/// distinct elements drawn after the round constants.
+///
+/// `select` skips that many candidate matrices, the reference implementation rejecting
+/// the insecure ones out of band.
+pub(crate) fn generate_mds<F: FieldExt, const WIDTH: usize>(
+    grain: &mut Grain<F>,
+    mut select: usize,
+) -> Mds<F, WIDTH> {
+    loop {
+        let (xs, ys) = loop {
+            let vals: Vec<F> =
+                (0..2 * WIDTH).map(|_| grain.next_field_element_without_rejection()).collect();
+
+            let distinct = vals.iter().enumerate().all(|(i, a)| vals[..i].iter().all(|b| a != b));
+            if distinct {
+                break (vals[..WIDTH].to_vec(), vals[WIDTH..].to_vec());
+            }
+        };
+
+        if select != 0 {
+            select -= 1;
+            continue;
+        }
+
+        let mut mds = [[F::zero(); WIDTH]; WIDTH];
+        for (row, x) in mds.iter_mut().zip(xs.iter()) {
+            for (entry, y) in row.iter_mut().zip(ys.iter()) {
+                // x_i + y_j = 0 is one of the insecure cases `select` rules out
+                *entry = (*x + y).invert().unwrap();
+            }
+        }
+        return mds;
+    }
+}
@@ -0,0 +1,388 @@
+use std::{fmt, marker::PhantomData};
+
+use halo2_proofs::arithmetic::FieldExt;
+
+use super::{
+    grain::Grain,
+    mds::{generate_mds, Mds},
+};
+
+/// The parameters of a Poseidon permutation with the x^5 S-box, over WIDTH field elements
+/// of which RATE are absorbed at a time.
+pub trait Spec<F: FieldExt, const WIDTH: usize, const RATE: usize>: fmt::Debug {
+    /// R_F, half of them before the partial rounds and half after
+    fn full_rounds() -> usize;
+
+    /// R_P
+    fn partial_rounds() -> usize;
+
+    /// Number of candidate MDS matrices the reference implementation rejects before the
+    /// first secure one.
+    fn secure_mds() -> usize;
+
+    /// The round constants, one array per round, and the MDS matrix, as generated by the
+    /// Grain LFSR of the reference implementation.
+    fn constants() -> (Vec<[F; WIDTH]>, Mds<F, WIDTH>) {
+        assert!(RATE < WIDTH, "the capacity must be at least one element");
+        let (full_rounds, partial_rounds) = (Self::full_rounds(), Self::partial_rounds());
+
+        let mut grain = Grain::new(WIDTH, full_rounds, partial_rounds);
+        let round_constants = (0..full_rounds + partial_rounds)
+            .map(|_| {
+                let mut rcs = [F::zero(); WIDTH];
+                for rc in rcs.iter_mut() {
+                    *rc = grain.next_field_element();
+                }
+                rcs
+            })
+            .collect();
+        let mds = generate_mds(&mut grain, Self::secure_mds());
+
+        (round_constants, mds)
+    }
+}
+
+/// Poseidon-128 with the x^5 S-box over 3 elements, of which 2 are absorbed at a time,
+/// with the R_F = 8 and R_P = 56 rounds used on either Pasta field by Zcash's Orchard.
+#[derive(Debug, Clone, Copy)]
+pub struct P128Pow5T3;
+
+impl<F: FieldExt> Spec<F, 3, 2> for P128Pow5T3 {
+    fn full_rounds() -> usize {
+        8
+    }
+
+    fn partial_rounds() -> usize {
+        56
+    }
+
+    fn secure_mds() -> usize {
+        0
+    }
+}
+
+pub(crate) fn sbox<F: FieldExt>(x: F) -> F {
+    x.square().square() * x
+}
+
+fn apply_mds<F: FieldExt, const WIDTH: usize>(state: &mut [F; WIDTH], mds: &Mds<F, WIDTH>) {
+    let mut new_state = [F::zero(); WIDTH];
+    for (word, row) in new_state.iter_mut().zip(mds.iter()) {
+        *word = row.iter().zip(state.iter()).fold(F::zero(), |acc, (m, s)| acc + *m * s);
+    }
+    *state = new_state;
+}
+
+/// Adds the round constants, applies the S-box to every word, then the MDS matrix.
+pub(crate) fn full_round<F: FieldExt, const WIDTH: usize>(
+    state: &mut [F; WIDTH],
+    rcs: &[F; WIDTH],
+    mds: &Mds<F, WIDTH>,
+) {
+    for (word, rc) in state.iter_mut().zip(rcs.iter()) {
+        *word = sbox(*word + rc);
+    }
+    apply_mds(state, mds);
+}
+
+/// As a full round, with the S-box applied to the first word only.
+pub(crate) fn partial_round<F: FieldExt, const WIDTH: usize>(
+    state: &mut [F; WIDTH],
+    rcs: &[F; WIDTH],
+    mds: &Mds<F, WIDTH>,
+) {
+    for (word, rc) in state.iter_mut().zip(rcs.iter()) {
+        *word += rc;
+    }
+    state[0] = sbox(state[0]);
+    apply_mds(state, mds);
+}
+
+/// Whether round `round` of a permutation with R_F full rounds and R_P partial ones is a
+/// full round.
+pub(crate) fn is_full_round(round: usize, full_rounds: usize, partial_rounds: usize) -> bool {
+    round < full_rounds / 2 || round >= full_rounds / 2 + partial_rounds
+}
+
+/// The Poseidon permutation.
+pub fn permute<F: FieldExt, S: Spec<F, WIDTH, RATE>, const WIDTH: usize, const RATE: usize>(
+    state: &mut [F; WIDTH],
+    mds: &Mds<F, WIDTH>,
+    round_constants: &[[F; WIDTH]],
+) {
+    let (full_rounds, partial_rounds) = (S::full_rounds(), S::partial_rounds());
+
+    for (round, rcs) in round_constants.iter().enumerate() {
+        if is_full_round(round, full_rounds, partial_rounds) {
+            full_round(state, rcs, mds);
+        } else {
+            partial_round(state, rcs, mds);
+        }
+    }
+}
+
+/// The capacity element of a hash of `len` elements, len * 2^64 as in the `ConstantLength`
+/// domain of the reference implementation, so inputs of different lengths never share a
+/// permutation.
+pub fn initial_capacity_element<F: FieldExt>(len: usize) -> F {
+    F::from_u128((len as u128) << 64)
+}
+
+#[derive(Debug)]
+enum Mode<F> {
+    /// The rate words added since the last permutation
+    Absorbing(Vec<F>),
+    /// The rate words not squeezed yet, in reverse order
+    Squeezing(Vec<F>),
+}
+
+/// A Poseidon sponge: elements are absorbed RATE at a time, the missing ones of the last
+/// block being zero, then squeezed RATE at a time.
+///
+/// ```text
+/// new(c) : state = [0, .., 0, c, 0, ..]       (c at word RATE)
+/// absorb : rate words += next RATE elements, then permute
+/// squeeze: rate words, then permute for more
+/// ```
+#[derive(Debug)]
+pub struct Sponge<F: FieldExt, S: Spec<F, WIDTH, RATE>, const WIDTH: usize, const RATE: usize> {
+    state: [F; WIDTH],
+    mode: Mode<F>,
+    round_constants: Vec<[F; WIDTH]>,
+    mds: Mds<F, WIDTH>,
+    _marker: PhantomData<S>,
+}
+
+impl<F: FieldExt, S: Spec<F, WIDTH, RATE>, const WIDTH: usize, const RATE: usize>
+    Sponge<F, S, WIDTH, RATE>
+{
+    pub fn new(initial_capacity_element: F) -> Self {
+        let (round_constants, mds) = S::constants();
+        Self::with_constants(initial_capacity_element, round_constants, mds)
+    }
+
+    fn with_constants(
+        initial_capacity_element: F,
+        round_constants: Vec<[F; WIDTH]>,
+        mds: Mds<F, WIDTH>,
+    ) -> Self {
+        let mut state = [F::zero(); WIDTH];
+        state[RATE] = initial_capacity_element;
+
+        Self { state, mode: Mode::Absorbing(vec![]), round_constants, mds, _marker: PhantomData }
+    }
+
+    fn absorb_and_permute(&mut self, block: &[F]) {
+        for (word, value) in self.state.iter_mut().zip(block.iter()) {
+            *word += value;
+        }
+        permute::<F, S, WIDTH, RATE>(&mut self.state, &self.mds, &self.round_constants);
+    }
+
+    /// Panics once squeezing has started.
+    pub fn absorb(&mut self, value: F) {
+        let block = match &mut self.mode {
+            Mode::Absorbing(block) => block,
+            Mode::Squeezing(_) => panic!("cannot absorb after squeezing"),
+        };
+
+        if block.len() == RATE {
+            let full = std::mem::take(block);
+            self.absorb_and_permute(&full);
+        }
+        match &mut self.mode {
+            Mode::Absorbing(block) => block.push(value),
+            Mode::Squeezing(_) => unreachable!(),
+        }
+    }
+
+    pub fn squeeze(&mut self) -> F {
+        if let Mode::Absorbing(block) = &mut self.mode {
+            let last = std::mem::take(block);
+            self.absorb_and_permute(&last);
+            self.mode = Mode::Squeezing(self.rate_words());
+        }
+
+        match &mut self.mode {
+            Mode::Squeezing(words) => match words.pop() {
+                Some(word) => word,
+                None => {
+                    self.absorb_and_permute(&[]);
+                    let mut words = self.rate_words();
+                    let word = words.pop().unwrap();
+                    self.mode = Mode::Squeezing(words);
+                    word
+                }
+            },
+            Mode::Absorbing(_) => unreachable!(),
+        }
+    }
+
+    fn rate_words(&self) -> Vec<F> {
+        self.state[..RATE].iter().rev().copied().collect()
+    }
+}
+
+/// Hashes `inputs` to one element, with their length in the capacity.
+pub fn hash<F: FieldExt, S: Spec<F, WIDTH, RATE>, const WIDTH: usize, const RATE: usize>(
+    inputs: &[F],
+) -> F {
+    Hasher::<F, S, WIDTH, RATE>::new().hash(inputs)
+}
+
+/// `hash` with the constants generated once, for callers hashing many times.
+#[derive(Debug, Clone)]
+pub struct Hasher<F: FieldExt, S: Spec<F, WIDTH, RATE>, const WIDTH: usize, const RATE: usize> {
+    round_constants: Vec<[F; WIDTH]>,
+    mds: Mds<F, WIDTH>,
+    _marker: PhantomData<S>,
+}
+
+impl<F: FieldExt, S: Spec<F, WIDTH, RATE>, const WIDTH: usize, const RATE: usize> Default
+    for Hasher<F, S, WIDTH, RATE>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: FieldExt, S: Spec<F, WIDTH, RATE>, const WIDTH: usize, const RATE: usize>
+    Hasher<F, S, WIDTH, RATE>
+{
+    pub fn new() -> Self {
+        let (round_constants, mds) = S::constants();
+        Self { round_constants, mds, _marker: PhantomData }
+    }
+
+    pub fn hash(&self, inputs: &[F]) -> F {
+        let mut sponge = Sponge::<F, S, WIDTH, RATE>::with_constants(
+            initial_capacity_element(inputs.len()),
+            self.round_constants.clone(),
+            self.mds,
+        );
+        for input in inputs {
+            sponge.absorb(*input);
+        }
+        sponge.squeeze()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::PrimeField;
+    use halo2_proofs::pasta::Fp;
+
+    use super::*;
+
+    fn fp(hex: &str) -> Fp {
+        let mut repr = [0u8; 32];
+        for (i, byte) in repr.iter_mut().rev().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 + 2 * i..4 + 2 * i], 16).unwrap();
+        }
+        Fp::from_repr(repr).unwrap()
+    }
+
+    #[test]
+    fn test_constants() {
+        let (round_constants, mds) = <P128Pow5T3 as Spec<Fp, 3, 2>>::constants();
+        assert_eq!(round_constants.len(), 64);
+
+        // the first round constant of the Orchard parameters, from
+        // `sage generate_parameters_grain.sage 1 0 255 3 8 56 <p>`
+        assert_eq!(
+            round_constants[0][0],
+            fp("0x360d7470611e473d353f628f76d110f34e71162f31003b7057538c2596426303")
+        );
+
+        // a Cauchy matrix is invertible, in particular no entry is 0
+        assert!(mds.iter().flatten().all(|entry| *entry != Fp::zero()));
+    }
+
+    #[test]
+    fn test_permute_test_vectors() {
+        // from zcash-test-vectors orchard_poseidon/permute/fp.py
+        let vectors = [
+            (
+                [Fp::zero(), Fp::one(), Fp::from(2)],
+                [
+                    "0x2a526acd0b64b45394efb364f966240ff7e69a71d0b642a0aeb1bc024aeca456",
+                    "0x13c5d1568b4aa43076ff7dae343d5512dcd42e7fbed9dafe012a3e9628e5b82a",
+                    "0x0a49c868c6976544256fcd597984561af7cfdfe1bda42c7b359029a1d34e9ddd",
+                ]
+                .map(fp),
+            ),
+            (
+                [
+                    "0x082169eef62efaaf9d9364b1666e4d4c07576bac4994133ffb70fcad738f7a5c",
+                    "0x0dcdb1cf014253b3c78849f2a39cefb0e6772b980e2e5d2aa6bde1f2b386dd1a",
+                    "0x1435a7304e9de2a5d6368e9c7e1fe01f27c7a99b670f59f20f94b63225b869bd",
+                ]
+                .map(fp),
+                [
+                    "0x21ddae20d1d6227a036952a16129fda2cd878092770c38e77e8a9238832f6ed0",
+                    "0x0d1451eeb8b98c361f1e4809bdf4e549c969c0d094ac79bcd6106f415fa45529",
+                    "0x105bf9ac68bb569d795cbe4430401cc587ab82d9b76f131e2b5dd6e9bb76830d",
+                ]
+                .map(fp),
+            ),
+        ];
+
+        let (round_constants, mds) = <P128Pow5T3 as Spec<Fp, 3, 2>>::constants();
+        for (mut state, expected) in vectors {
+            permute::<Fp, P128Pow5T3, 3, 2>(&mut state, &mds, &round_constants);
+            assert_eq!(state, expected);
+        }
+    }
+
+    #[test]
+    fn test_hash_test_vectors() {
+        // from zcash-test-vectors orchard_poseidon/hash/fp.py
+        let vectors = [
+            (
+                [Fp::zero(), Fp::one()],
+                "0x062ff1c32bb0ef109d6a1bc9399a083eed83c2a7fb54cdbe389d32a011d75883",
+            ),
+            (
+                [
+                    fp("0x082169eef62efaaf9d9364b1666e4d4c07576bac4994133ffb70fcad738f7a5c"),
+                    fp("0x0dcdb1cf014253b3c78849f2a39cefb0e6772b980e2e5d2aa6bde1f2b386dd1a"),
+                ],
+                "0x03e63b302667d2794b3992be2385a0f18e2ac0ca61ded5c430fef83eff7526db",
+            ),
+            (
+                [
+                    fp("0x1435a7304e9de2a5d6368e9c7e1fe01f27c7a99b670f59f20f94b63225b869bd"),
+                    fp("0x0e81632a9168694bb6bcec2f7bfac26208c05aed4828f99ebeafd655429850bc"),
+                ],
+                "0x3d141ddccd07b05ee6417a8ceff63100763d189c0fac96a88dfe5c1d1e1d12f5",
+            ),
+        ];
+
+        for (message, expected) in vectors {
+            assert_eq!(hash::<Fp, P128Pow5T3, 3, 2>(&message), fp(expected));
+        }
+    }
+
+    #[test]
+    fn test_sponge() {
+        let inputs: Vec<Fp> = (0..5).map(Fp::from).collect();
+
+        // the length is bound to the digest, zero padding included
+        let digest = hash::<Fp, P128Pow5T3, 3, 2>(&inputs);
+        let mut padded = inputs.clone();
+        padded.push(Fp::zero());
+        assert_ne!(hash::<Fp, P128Pow5T3, 3, 2>(&padded), digest);
+
+        // squeezing one block after another
+        let mut sponge = Sponge::<Fp, P128Pow5T3, 3, 2>::new(Fp::zero());
+        sponge.absorb(Fp::one());
+        let squeezed: Vec<_> = (0..3).map(|_| sponge.squeeze()).collect();
+
+        let (round_constants, mds) = <P128Pow5T3 as Spec<Fp, 3, 2>>::constants();
+        let mut state = [Fp::one(), Fp::zero(), Fp::zero()];
+        permute::<Fp, P128Pow5T3, 3, 2>(&mut state, &mds, &round_constants);
+        assert_eq!(squeezed[..2], state[..2]);
+        permute::<Fp, P128Pow5T3, 3, 2>(&mut state, &mds, &round_constants);
+        assert_eq!(squeezed[2], state[0]);
+    }
+}
@@ -0,0 +1,66 @@
+//! End-to-end proving pipeline over the Pasta IPA backend.
+//!
+//! The circuit is defined over Fp, the scalar field of Vesta, so commitments live on
+//! Vesta (`EqAffine`) and no trusted setup is needed: `Params::new(k)` deterministically
+//! derives the generators for 2^k rows.
+//!
+//! ```text
+//! setup  : (k, circuit)                    -> (params, pk)
+//! prove  : (params, pk, circuit, instance) -> proof
+//! verify : (params, vk, proof, instance)   -> Ok(()) | Err(_)
+//! ```
+//!
+//! Public inputs are passed as one slice per instance column.
+
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{
+        create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, Error, ProvingKey,
+        SingleVerifier, VerifyingKey,
+    },
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255},
+};
+use rand::rngs::OsRng;
+
+/// Generates the commitment parameters for 2^k rows, then the verifying and proving keys.
+/// Keys only depend on the circuit shape, so they are generated without witnesses.
+pub fn setup<C: Circuit<Fp>>(
+    k: u32,
+    circuit: &C,
+) -> Result<(Params<EqAffine>, ProvingKey<EqAffine>), Error> {
+    let params: Params<EqAffine> = Params::new(k);
+
+    let empty_circuit = circuit.without_witnesses();
+    let vk = keygen_vk(&params, &empty_circuit)?;
+    let pk = keygen_pk(&params, vk, &empty_circuit)?;
+
+    Ok((params, pk))
+}
+
+/// Creates a proof for `circuit`, hashing the transcript with Blake2b.
+pub fn prove<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: C,
+    public_inputs: &[&[Fp]],
+) -> Result<Vec<u8>, Error> {
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+
+    create_proof(params, pk, &[circuit], &[public_inputs], OsRng, &mut transcript)?;
+
+    Ok(transcript.finalize())
+}
+
+/// Checks `proof` against the verifying key and the claimed public inputs.
+pub fn verify(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proof: &[u8],
+    public_inputs: &[&[Fp]],
+) -> Result<(), Error> {
+    let strategy = SingleVerifier::new(params);
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+
+    verify_proof(params, vk, strategy, &[public_inputs], &mut transcript)
+}
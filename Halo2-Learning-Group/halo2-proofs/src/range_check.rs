@@ -1,4 +1,19 @@
-mod decompose_range_check;
+// the examples not used by the chip are only exercised by their own tests
+pub mod bits;
+pub mod bounded_range_check;
+pub mod canonical_decompose;
+pub mod chip;
+pub mod comparison;
+pub mod decompose_range_check;
 mod example1;
+#[allow(dead_code)]
 mod example2;
+#[allow(dead_code)]
 mod example3;
+pub mod table;
+
+pub use bits::{BitsChip, BitsConfig, BitsInstructions};
+pub use bounded_range_check::{Bound, BoundedRangeConfig};
+pub use chip::{RangeCheckChip, RangeCheckChipConfig, RangeCheckInstructions, Strategy};
+pub use comparison::{ComparisonChip, ComparisonConfig, ComparisonInstructions};
+pub use table::{TableKind, TableRegistry};
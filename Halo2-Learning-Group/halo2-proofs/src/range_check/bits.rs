@@ -0,0 +1,333 @@
+use std::marker::PhantomData;
+
+use ff::PrimeFieldBits;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+/// Conversions between a number and its little-endian bits, as the circom `Num2Bits` and
+/// `Bits2Num` templates.
+pub trait BitsInstructions<F: FieldExt> {
+    /// Returns the `num_bits` little-endian bits of `value`, which must fit in them, or
+    /// `Error::Synthesis` if `num_bits` is 0.
+    fn num_to_bits(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error>;
+
+    /// Returns Σ b_i 2^i, after constraining every b_i to be a bit, or `Error::Synthesis` if
+    /// `bits` is empty.
+    fn bits_to_num(
+        &self,
+        layouter: impl Layouter<F>,
+        bits: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error>;
+}
+
+/// Each bit is constrained to be boolean on its own row, and the number is recomposed from
+/// the most significant bit down with acc_i = 2 * acc_{i-1} + b_i:
+///
+/// ```text
+/// |   bit   |      acc       | q_bit | q_first | q_horner |
+/// ---------------------------------------------------------
+/// | b_{n-1} |    b_{n-1}     |   1   |    1    |    0     |
+/// | b_{n-2} | 2 acc + b_{n-2}|   1   |    0    |    1     |
+/// |   ...   |      ...       |  ...  |   ...   |   ...    |
+/// |   b_0   |     value      |   1   |    0    |    1     |
+/// ```
+#[derive(Debug, Clone)]
+pub struct BitsConfig<F: FieldExt> {
+    pub bit: Column<Advice>,
+    pub acc: Column<Advice>,
+    q_bit: Selector,
+    q_first: Selector,
+    q_horner: Selector,
+    _marker: PhantomData<F>,
+}
+
+/// The little-endian bit cells and the last accumulator of a layout
+struct Recomposition<F: FieldExt> {
+    bits: Vec<AssignedCell<F, F>>,
+    num: AssignedCell<F, F>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BitsChip<F: FieldExt> {
+    config: BitsConfig<F>,
+}
+
+impl<F: FieldExt + PrimeFieldBits> BitsChip<F> {
+    pub fn construct(config: BitsConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        bit: Column<Advice>,
+        acc: Column<Advice>,
+    ) -> BitsConfig<F> {
+        meta.enable_equality(bit);
+        meta.enable_equality(acc);
+
+        let q_bit = meta.selector();
+        let q_first = meta.selector();
+        let q_horner = meta.selector();
+
+        meta.create_gate("boolean bit", |meta| {
+            let q_bit = meta.query_selector(q_bit);
+            let b = meta.query_advice(bit, Rotation::cur());
+
+            let one = Expression::Constant(F::one());
+
+            vec![q_bit * b.clone() * (one - b)]
+        });
+
+        meta.create_gate("most significant bit", |meta| {
+            let q_first = meta.query_selector(q_first);
+            let b = meta.query_advice(bit, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+
+            vec![q_first * (acc - b)]
+        });
+
+        // separate from the first row, which has no previous accumulator
+        meta.create_gate("horner", |meta| {
+            let q_horner = meta.query_selector(q_horner);
+            let b = meta.query_advice(bit, Rotation::cur());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+
+            vec![q_horner * (acc_cur - acc_prev * F::from(2) - b)]
+        });
+
+        BitsConfig { bit, acc, q_bit, q_first, q_horner, _marker: PhantomData }
+    }
+
+    /// Lays out the bits from the most significant one and returns the recomposed number.
+    ///
+    /// The accumulators are doubled in the field rather than recomposed with `lebs2ip`, which
+    /// stops at 64 bits while `num_to_bits` goes up to the bit length of the modulus.
+    fn assign_horner(
+        &self,
+        region: &mut Region<'_, F>,
+        bits: &[Value<F>],
+    ) -> Result<Recomposition<F>, Error> {
+        let config = &self.config;
+        // there is no row to hold the number of an empty bit string
+        if bits.is_empty() {
+            return Err(Error::Synthesis);
+        }
+
+        let mut bit_cells = vec![];
+        let mut acc: Option<AssignedCell<F, F>> = None;
+
+        for (offset, b) in bits.iter().rev().enumerate() {
+            config.q_bit.enable(region, offset)?;
+
+            let bit = region.assign_advice(|| "bit", config.bit, offset, || *b)?;
+
+            let value = match &acc {
+                None => {
+                    config.q_first.enable(region, offset)?;
+                    *b
+                }
+                Some(acc) => {
+                    config.q_horner.enable(region, offset)?;
+                    acc.value().zip(*b).map(|(acc, b)| acc.double() + b)
+                }
+            };
+            acc = Some(region.assign_advice(|| "acc", config.acc, offset, || value)?);
+
+            bit_cells.push(bit);
+        }
+
+        // back to little-endian
+        bit_cells.reverse();
+        Ok(Recomposition { bits: bit_cells, num: acc.unwrap() })
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits> BitsInstructions<F> for BitsChip<F> {
+    /// `num_bits` must be below the bit length of the modulus, otherwise several bit strings
+    /// recompose to the same element; see `CanonicalDecomposeConfig` for full elements.
+    fn num_to_bits(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        assert!(num_bits < F::NUM_BITS as usize);
+
+        let bits: Vec<Value<F>> = value
+            .value()
+            .map(|v| {
+                let bits = v.to_le_bits();
+                bits.iter().by_vals().take(num_bits).map(|b| F::from(b as u64)).collect::<Vec<_>>()
+            })
+            .transpose_vec(num_bits);
+
+        layouter.assign_region(
+            || "num to bits",
+            |mut region| {
+                let Recomposition { bits, num } = self.assign_horner(&mut region, &bits)?;
+                region.constrain_equal(num.cell(), value.cell())?;
+                Ok(bits)
+            },
+        )
+    }
+
+    fn bits_to_num(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bits: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let values: Vec<_> = bits.iter().map(|b| b.value().copied()).collect();
+
+        layouter.assign_region(
+            || "bits to num",
+            |mut region| {
+                let Recomposition { bits: copies, num } =
+                    self.assign_horner(&mut region, &values)?;
+                for (bit, copy) in bits.iter().zip(copies.iter()) {
+                    region.constrain_equal(bit.cell(), copy.cell())?;
+                }
+                Ok(num)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{
+        circuit::floor_planner::V1,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::{dev::assert_witness_sound, range_check::decompose_range_check::lebs2ip};
+
+    /// Decomposes a private input and exposes its bits, then recomposes them and exposes the
+    /// number after them, as `Num2Bits` followed by `Bits2Num`
+    #[derive(Clone)]
+    struct MyCircuit {
+        value: Value<Fp>,
+        num_bits: usize,
+    }
+
+    impl Circuit<Fp> for MyCircuit {
+        type Config = (BitsConfig<Fp>, Column<Instance>);
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self { value: Value::unknown(), num_bits: self.num_bits }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let bit = meta.advice_column();
+            let acc = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            (BitsChip::configure(meta, bit, acc), instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = BitsChip::construct(config.clone());
+
+            let value = layouter.assign_region(
+                || "Witness in",
+                |mut region| region.assign_advice(|| "in", config.acc, 0, || self.value),
+            )?;
+
+            let bits =
+                chip.num_to_bits(layouter.namespace(|| "Num2Bits"), &value, self.num_bits)?;
+            for (row, bit) in bits.iter().enumerate() {
+                layouter.constrain_instance(bit.cell(), instance, row)?;
+            }
+
+            let num = chip.bits_to_num(layouter.namespace(|| "Bits2Num"), &bits)?;
+            layouter.constrain_instance(num.cell(), instance, self.num_bits)
+        }
+    }
+
+    fn bits_of(value: u64, num_bits: usize) -> Vec<bool> {
+        (0..num_bits).map(|i| (value >> i) & 1 == 1).collect()
+    }
+
+    fn public_input(bits: &[bool], num: Fp) -> Vec<Vec<Fp>> {
+        let mut instance: Vec<_> = bits.iter().map(|b| Fp::from(*b as u64)).collect();
+        instance.push(num);
+        vec![instance]
+    }
+
+    #[test]
+    fn test_num2bits() {
+        let k = 9;
+
+        // Num2Bits(5) with "in": "11"
+        let bits = bits_of(11, 5);
+        assert_eq!(bits, [true, true, false, true, false]);
+        assert_eq!(lebs2ip(&bits), 11);
+
+        let circuit = MyCircuit { value: Value::known(Fp::from(11)), num_bits: 5 };
+        let prover = MockProver::run(k, &circuit, public_input(&bits, Fp::from(11))).unwrap();
+        prover.assert_satisfied();
+
+        // 32 does not fit in 5 bits
+        let circuit = MyCircuit { value: Value::known(Fp::from(32)), num_bits: 5 };
+        let prover = MockProver::run(k, &circuit, public_input(&bits_of(0, 5), Fp::from(32)));
+        assert!(prover.unwrap().verify().is_err());
+
+        // wrong bits
+        let circuit = MyCircuit { value: Value::known(Fp::from(11)), num_bits: 5 };
+        let wrong = bits_of(13, 5);
+        let prover = MockProver::run(k, &circuit, public_input(&wrong, Fp::from(11))).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_zero_bits() {
+        let circuit = MyCircuit { value: Value::known(Fp::zero()), num_bits: 0 };
+        let prover = MockProver::run(9, &circuit, public_input(&[], Fp::zero()));
+        assert!(matches!(prover, Err(Error::Synthesis)));
+    }
+
+    #[test]
+    fn test_less_than() {
+        // Num2Bits and Bits2Num take 253 rows each
+        let k = 10;
+        let n = 252;
+
+        // LessThan(252) with "in": ["1", "2"], through Num2Bits(253) of in[0] + 2^252 - in[1]
+        for (a, b, lt) in [(1u64, 2u64, true), (2, 1, false), (5, 5, false)] {
+            let shifted = Fp::from(a) + Fp::from(2).pow_vartime([n as u64]) - Fp::from(b);
+
+            let bits: Vec<bool> = shifted.to_le_bits().iter().by_vals().take(n + 1).collect();
+            // out = 1 - bit n
+            assert_eq!(!bits[n], lt);
+
+            let circuit = MyCircuit { value: Value::known(shifted), num_bits: n + 1 };
+            let prover = MockProver::run(k, &circuit, public_input(&bits, shifted)).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_bits_witness_sound() {
+        let circuit = MyCircuit { value: Value::known(Fp::from(11)), num_bits: 5 };
+        assert_witness_sound(9, circuit, public_input(&bits_of(11, 5), Fp::from(11)));
+    }
+}
@@ -0,0 +1,343 @@
+use ff::PrimeFieldBits;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+/// Bit length of the Pasta moduli, p = 2^254 + t_p
+pub const NUM_BITS: usize = 255;
+
+/// Bit length of the low part checked against t_p, t_p < 2^LOW_BITS
+const LOW_BITS: usize = 130;
+
+/// This gadget decomposes a full field element into its canonical 255 little-endian bits.
+///
+/// A running sum over single bits, z_{i+1} = (z_i - b_i) / 2 with z_255 = 0, only proves
+/// that the integer N = Σ b_i 2^i is congruent to the value: as 2^255 > p, both v and v + p
+/// may have 255 bits. The decomposition is canonical iff N < p.
+///
+/// Writing N = α_0 + 2^254 α_1 and p = 2^254 + t_p:
+///
+/// ```text
+/// α_1 = 0  =>  N < 2^254 < p
+/// α_1 = 1  =>  N < p  iff  α_0 < t_p
+/// ```
+///
+/// As t_p < 2^130, α_0 < t_p is checked when α_1 = 1 by requiring bits 130..253 to be 0,
+/// i.e. z_130 = 2^124, and by decomposing w = α_0 + 2^130 - t_p to 130 bits. Over the
+/// field, -t_p = 2^254 so w = α_1 (z_0 - 2^130 z_130 + 2^130 + 2^254), 0 when α_1 = 0.
+///
+/// ```text
+/// | running_sum |  bits   |  high   | q_bit | q_canonical |
+/// ---------------------------------------------------------
+/// |     z_0     |   b_0   |         |   1   |      0      |
+/// |     ...     |   ...   |         |  ...  |     ...     |
+/// |   z_254     |  b_254  |         |   1   |      0      |
+/// |   z_255     |         |         |   0   |      0      |
+/// |     z_0     |   α_1   |  z_130  |   0   |      1      |
+/// |  w = z'_0   |  b'_0   |         |   1   |      0      |
+/// |     ...     |   ...   |         |  ...  |     ...     |
+/// |   z'_130    |         |         |   0   |      0      |
+/// ```
+///
+/// z_255 and z'_130 are constrained to 0 with a constants column, which the caller must
+/// enable.
+#[derive(Debug, Clone)]
+pub struct CanonicalDecomposeConfig<F: FieldExt + PrimeFieldBits> {
+    pub(crate) running_sum: Column<Advice>,
+    bits: Column<Advice>,
+    high: Column<Advice>,
+    q_bit: Selector,
+    q_canonical: Selector,
+    _marker: std::marker::PhantomData<F>,
+}
+
+fn two_pow<F: FieldExt>(n: usize) -> F {
+    F::from(2).pow_vartime([n as u64])
+}
+
+/// Cells produced by `CanonicalDecomposeConfig::assign`.
+#[derive(Debug, Clone)]
+pub struct CanonicalDecomposition<F: FieldExt> {
+    /// The 255 little-endian bits of the canonical representative of the value
+    pub bits: Vec<AssignedCell<F, F>>,
+    /// The running sum {z_0, ..., z_255}, z_i holding the value shifted right by i bits
+    pub running_sum: Vec<AssignedCell<F, F>>,
+}
+
+impl<F: FieldExt + PrimeFieldBits> CanonicalDecomposeConfig<F> {
+    /// Only supports moduli 2^254 + t with t < 2^130, such as the Pasta ones.
+    pub fn configure(meta: &mut ConstraintSystem<F>, running_sum: Column<Advice>) -> Self {
+        let modulus: Vec<bool> = F::char_le_bits().iter().by_vals().take(NUM_BITS).collect();
+        assert_eq!(F::NUM_BITS as usize, NUM_BITS);
+        assert!(modulus[NUM_BITS - 1] && modulus[LOW_BITS..NUM_BITS - 1].iter().all(|b| !b));
+
+        let bits = meta.advice_column();
+        let high = meta.advice_column();
+        for column in [running_sum, bits, high] {
+            meta.enable_equality(column);
+        }
+
+        let q_bit = meta.selector();
+        let q_canonical = meta.selector();
+
+        meta.create_gate("bit", |meta| {
+            let q_bit = meta.query_selector(q_bit);
+
+            let z_cur = meta.query_advice(running_sum, Rotation::cur());
+            let z_next = meta.query_advice(running_sum, Rotation::next());
+            let b = meta.query_advice(bits, Rotation::cur());
+
+            let one = Expression::Constant(F::one());
+
+            Constraints::with_selector(
+                q_bit,
+                [
+                    ("b = z_cur - 2 z_next", b.clone() - (z_cur - z_next * F::from(2))),
+                    ("boolean bit", b.clone() * (one - b)),
+                ],
+            )
+        });
+
+        meta.create_gate("canonical", |meta| {
+            let q_canonical = meta.query_selector(q_canonical);
+
+            let z_0 = meta.query_advice(running_sum, Rotation::cur());
+            let alpha_1 = meta.query_advice(bits, Rotation::cur());
+            let z_130 = meta.query_advice(high, Rotation::cur());
+            let w = meta.query_advice(running_sum, Rotation::next());
+
+            // α_0 + 2^130 - t_p, with -t_p = 2^254 over the field
+            let alpha_0 = z_0 - z_130.clone() * two_pow::<F>(LOW_BITS);
+            let shifted =
+                alpha_0 + Expression::Constant(two_pow::<F>(LOW_BITS) + two_pow::<F>(254));
+
+            Constraints::with_selector(
+                q_canonical,
+                [
+                    (
+                        "bits 130..253 are 0",
+                        alpha_1.clone()
+                            * (z_130 - Expression::Constant(two_pow::<F>(NUM_BITS - 1 - LOW_BITS))),
+                    ),
+                    ("w = α_1 (α_0 + 2^130 - t_p)", w - alpha_1 * shifted),
+                ],
+            )
+        });
+
+        Self { running_sum, bits, high, q_bit, q_canonical, _marker: std::marker::PhantomData }
+    }
+
+    /// Decomposes `value` into its canonical little-endian bits.
+    pub fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        value: AssignedCell<F, F>,
+    ) -> Result<CanonicalDecomposition<F>, Error> {
+        let bits = value.value().map(|v| v.to_le_bits().iter().by_vals().take(NUM_BITS).collect());
+        self.assign_bits(layouter, value, bits)
+    }
+
+    /// Decomposes `value` with the given witness bits, which are not necessarily canonical.
+    fn assign_bits(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: AssignedCell<F, F>,
+        bits: Value<Vec<bool>>,
+    ) -> Result<CanonicalDecomposition<F>, Error> {
+        layouter.assign_region(
+            || "canonical decomposition",
+            |mut region| {
+                let CanonicalDecomposition { bits, running_sum: zs } =
+                    self.assign_running_sum(&mut region, 0, bits.clone(), NUM_BITS)?;
+                region.constrain_equal(value.cell(), zs[0].cell())?;
+
+                // α_1 is the top bit, the low part is what is left below z_130
+                let offset = NUM_BITS + 1;
+                self.q_canonical.enable(&mut region, offset)?;
+                zs[0].copy_advice(|| "z_0", &mut region, self.running_sum, offset)?;
+                let alpha_1 =
+                    bits[NUM_BITS - 1].copy_advice(|| "α_1", &mut region, self.bits, offset)?;
+                let z_130 = zs[LOW_BITS].copy_advice(|| "z_130", &mut region, self.high, offset)?;
+
+                let w: Value<F> = alpha_1.value().zip(z_130.value()).zip(zs[0].value()).map(
+                    |((alpha_1, z_130), z_0)| {
+                        let two_pow_130 = two_pow::<F>(LOW_BITS);
+                        *alpha_1 * (*z_0 - *z_130 * two_pow_130 + two_pow_130 + two_pow::<F>(254))
+                    },
+                );
+                let w_bits = w.map(|w| w.to_le_bits().iter().by_vals().take(LOW_BITS).collect());
+                self.assign_running_sum(&mut region, offset + 1, w_bits, LOW_BITS)?;
+
+                Ok(CanonicalDecomposition { bits, running_sum: zs })
+            },
+        )
+    }
+
+    /// Assigns the running sum of `num_bits` bits from `offset`, ending in 0.
+    fn assign_running_sum(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        bits: Value<Vec<bool>>,
+        num_bits: usize,
+    ) -> Result<CanonicalDecomposition<F>, Error> {
+        let bits = bits.transpose_vec(num_bits);
+
+        // z_i = Σ_{j ≥ i} b_j 2^{j - i}, from the top bit down
+        let mut zs = vec![Value::known(F::zero())];
+        for bit in bits.iter().rev() {
+            let z = zs.last().unwrap().zip(*bit).map(|(z, b)| z.double() + F::from(b as u64));
+            zs.push(z);
+        }
+        zs.reverse();
+
+        let mut z_cells = vec![];
+        for (i, z) in zs.into_iter().enumerate() {
+            z_cells.push(region.assign_advice(
+                || format!("z_{}", i),
+                self.running_sum,
+                offset + i,
+                || z,
+            )?);
+        }
+        region.constrain_constant(z_cells[num_bits].cell(), F::zero())?;
+
+        let mut bit_cells = vec![];
+        for (i, bit) in bits.into_iter().enumerate() {
+            self.q_bit.enable(region, offset + i)?;
+            bit_cells.push(region.assign_advice(
+                || format!("b_{}", i),
+                self.bits,
+                offset + i,
+                || bit.map(|b| F::from(b as u64)),
+            )?);
+        }
+
+        Ok(CanonicalDecomposition { bits: bit_cells, running_sum: z_cells })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{circuit::floor_planner::V1, dev::MockProver, pasta::Fp, plonk::Circuit};
+    use rand::rngs::OsRng;
+
+    use super::*;
+    use crate::dev::assert_witness_sound;
+
+    /// Decomposes `value`, with `bits` overriding the honest witness if set
+    #[derive(Clone)]
+    struct MyCircuit {
+        value: Value<Fp>,
+        bits: Option<Vec<bool>>,
+    }
+
+    impl Circuit<Fp> for MyCircuit {
+        type Config = CanonicalDecomposeConfig<Fp>;
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self { value: Value::unknown(), bits: None }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            let running_sum = meta.advice_column();
+            CanonicalDecomposeConfig::configure(meta, running_sum)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let value = layouter.assign_region(
+                || "Witness value",
+                |mut region| region.assign_advice(|| "value", config.running_sum, 0, || self.value),
+            )?;
+
+            let decomposition = match &self.bits {
+                None => config.assign(layouter.namespace(|| "decompose"), value)?,
+                Some(bits) => config.assign_bits(
+                    layouter.namespace(|| "decompose"),
+                    value,
+                    Value::known(bits.clone()),
+                )?,
+            };
+            assert_eq!(decomposition.bits.len(), NUM_BITS);
+
+            Ok(())
+        }
+    }
+
+    fn le_bits(value: Fp) -> Vec<bool> {
+        value.to_le_bits().iter().by_vals().take(NUM_BITS).collect()
+    }
+
+    fn modulus_bits() -> Vec<bool> {
+        Fp::char_le_bits().iter().by_vals().take(NUM_BITS).collect()
+    }
+
+    /// Integer addition of two little-endian bit strings, dropping the final carry
+    fn add_bits(a: &[bool], b: &[bool]) -> Vec<bool> {
+        let mut carry = false;
+        a.iter()
+            .zip(b.iter())
+            .map(|(a, b)| {
+                let sum = *a as u8 + *b as u8 + carry as u8;
+                carry = sum > 1;
+                sum % 2 == 1
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_canonical_decompose() {
+        let k = 9;
+
+        let two_pow_254 = two_pow::<Fp>(254);
+        let mut values =
+            vec![Fp::zero(), Fp::one(), -Fp::one(), two_pow_254, two_pow_254 - Fp::one()];
+        values.extend((0..5).map(|_| Fp::random(OsRng)));
+
+        for value in values {
+            let circuit = MyCircuit { value: Value::known(value), bits: None };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_canonical_decompose_aliasing() {
+        let k = 9;
+
+        // p itself, as the bits of 0
+        let circuit = MyCircuit { value: Value::known(Fp::zero()), bits: Some(modulus_bits()) };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // v + p < 2^255 for small values
+        for value in [Fp::one(), Fp::from(1 << 40), two_pow::<Fp>(254) - Fp::from_u128(u128::MAX)] {
+            let aliased = add_bits(&le_bits(value), &modulus_bits());
+            assert_ne!(aliased, le_bits(value));
+
+            let circuit = MyCircuit { value: Value::known(value), bits: Some(aliased) };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_canonical_decompose_witness_sound() {
+        for value in [Fp::from(5), -Fp::one()] {
+            let circuit = MyCircuit { value: Value::known(value), bits: None };
+            assert_witness_sound(9, circuit, vec![]);
+        }
+    }
+}
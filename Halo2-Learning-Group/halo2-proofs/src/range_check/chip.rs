@@ -0,0 +1,446 @@
+use ff::PrimeFieldBits;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter},
+    plonk::{Advice, Assigned, Column, ConstraintSystem, Error},
+};
+
+use super::{
+    decompose_range_check::DecomposeConfig, example1::RangeCheckConfig, table::TableRegistry,
+};
+
+/// Range checks over cells of the circuit.
+pub trait RangeCheckInstructions<F: FieldExt> {
+    /// Constrains `value` to fit in `num_bits` bits, i.e. 0 ≤ value < 2^num_bits.
+    fn range_check(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<(), Error>;
+
+    /// Constrains every cell of `values` to fit in `num_bits` bits.
+    ///
+    /// Defaults to one `range_check` per value, chips may lay the checks out together.
+    fn range_check_batch(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[AssignedCell<F, F>],
+        num_bits: usize,
+    ) -> Result<(), Error> {
+        for (i, value) in values.iter().enumerate() {
+            self.range_check(layouter.namespace(|| format!("range check {}", i)), value, num_bits)?;
+        }
+        Ok(())
+    }
+}
+
+/// How `RangeCheckChip` checks a given bit length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// v * (1 - v) * ... * (2^n - 1 - v) = 0, for n ≤ `POLYNOMIAL_MAX_BITS`
+    Polynomial,
+    /// A single row of the running sum, for n ≤ K
+    Lookup,
+    /// A running sum of K-bit chunks, plus a partial chunk if K does not divide n
+    Decomposition,
+}
+
+/// Largest bit length checked with a polynomial, the degree of the gate growing as 2^n
+pub const POLYNOMIAL_MAX_BITS: usize = 2;
+
+#[derive(Debug, Clone)]
+pub struct RangeCheckChipConfig<
+    F: FieldExt + PrimeFieldBits,
+    const LOOKUP_NUM_BITS: usize,
+    const LOOKUP_RANGE: usize,
+> {
+    pub value: Column<Advice>,
+    // one polynomial gate per small bit length
+    one_bit: RangeCheckConfig<F, 2>,
+    two_bits: RangeCheckConfig<F, 4>,
+    decompose: DecomposeConfig<F, LOOKUP_NUM_BITS, LOOKUP_RANGE>,
+}
+
+/// Range-checks cells with the cheapest strategy for their bit length, using a K-bit lookup
+/// table with K = `LOOKUP_NUM_BITS`.
+///
+/// The table comes from the circuit's `TableRegistry`, which loads it, and a constants column
+/// must be enabled for the running sums ending in 0.
+#[derive(Debug, Clone)]
+pub struct RangeCheckChip<
+    F: FieldExt + PrimeFieldBits,
+    const LOOKUP_NUM_BITS: usize,
+    const LOOKUP_RANGE: usize,
+> {
+    config: RangeCheckChipConfig<F, LOOKUP_NUM_BITS, LOOKUP_RANGE>,
+}
+
+impl<F: FieldExt + PrimeFieldBits, const LOOKUP_NUM_BITS: usize, const LOOKUP_RANGE: usize>
+    RangeCheckChip<F, LOOKUP_NUM_BITS, LOOKUP_RANGE>
+{
+    pub fn construct(config: RangeCheckChipConfig<F, LOOKUP_NUM_BITS, LOOKUP_RANGE>) -> Self {
+        Self { config }
+    }
+
+    /// Every strategy works in the single `value` column.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        value: Column<Advice>,
+        tables: &mut TableRegistry<F>,
+    ) -> RangeCheckChipConfig<F, LOOKUP_NUM_BITS, LOOKUP_RANGE> {
+        let one_bit = RangeCheckConfig::configure(meta, value);
+        let two_bits = RangeCheckConfig::configure(meta, value);
+        // enables equality on `value`
+        let decompose = DecomposeConfig::configure(meta, value, tables);
+
+        RangeCheckChipConfig { value, one_bit, two_bits, decompose }
+    }
+
+    pub fn strategy(num_bits: usize) -> Strategy {
+        match num_bits {
+            1..=POLYNOMIAL_MAX_BITS => Strategy::Polynomial,
+            _ if num_bits <= LOOKUP_NUM_BITS => Strategy::Lookup,
+            _ => Strategy::Decomposition,
+        }
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits, const LOOKUP_NUM_BITS: usize, const LOOKUP_RANGE: usize>
+    RangeCheckInstructions<F> for RangeCheckChip<F, LOOKUP_NUM_BITS, LOOKUP_RANGE>
+{
+    fn range_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<(), Error> {
+        match Self::strategy(num_bits) {
+            Strategy::Polynomial => {
+                let witness = value.value().map(Assigned::from);
+                let checked = if num_bits == 1 {
+                    self.config.one_bit.assign(layouter.namespace(|| "one bit"), witness)?
+                } else {
+                    self.config.two_bits.assign(layouter.namespace(|| "two bits"), witness)?
+                };
+
+                // the polynomial gates witness their own value, tie it to the input
+                layouter.assign_region(
+                    || "copy checked value",
+                    |mut region| region.constrain_equal(value.cell(), checked.cell()),
+                )
+            }
+            Strategy::Lookup | Strategy::Decomposition => {
+                self.config.decompose.assign(
+                    layouter.namespace(|| "decompose"),
+                    value.clone(),
+                    num_bits,
+                )?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Values of 1 to K bits share a single region, one lookup row each, longer ones are
+    /// decomposed one by one, as are 0-bit ones, which `range_check` constrains to 0.
+    fn range_check_batch(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[AssignedCell<F, F>],
+        num_bits: usize,
+    ) -> Result<(), Error> {
+        if num_bits == 0 || num_bits > LOOKUP_NUM_BITS {
+            for (i, value) in values.iter().enumerate() {
+                self.range_check(
+                    layouter.namespace(|| format!("range check {}", i)),
+                    value,
+                    num_bits,
+                )?;
+            }
+            return Ok(());
+        }
+
+        self.config.decompose.assign_batch(layouter.namespace(|| "batch"), values, num_bits)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{floor_planner::V1, Value},
+        dev::MockProver,
+        pasta::Fp,
+        plonk::Circuit,
+    };
+
+    use super::*;
+    use crate::dev::assert_witness_sound;
+
+    #[derive(Clone)]
+    struct MyCircuit {
+        value: Value<Fp>,
+        num_bits: usize,
+    }
+
+    impl Circuit<Fp> for MyCircuit {
+        type Config = (RangeCheckChipConfig<Fp, 8, 256>, TableRegistry<Fp>);
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self { value: Value::unknown(), num_bits: self.num_bits }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            let value = meta.advice_column();
+            let mut tables = TableRegistry::new();
+            (RangeCheckChip::configure(meta, value, &mut tables), tables)
+        }
+
+        fn synthesize(
+            &self,
+            (config, tables): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            tables.load(&mut layouter)?;
+            let chip = RangeCheckChip::construct(config.clone());
+
+            let value = layouter.assign_region(
+                || "Witness value",
+                |mut region| region.assign_advice(|| "value", config.value, 0, || self.value),
+            )?;
+
+            chip.range_check(layouter.namespace(|| "range check"), &value, self.num_bits)
+        }
+    }
+
+    /// Range-checks all of its values with one batch
+    #[derive(Clone)]
+    struct BatchCircuit {
+        values: Vec<Value<Fp>>,
+        num_bits: usize,
+    }
+
+    impl Circuit<Fp> for BatchCircuit {
+        type Config = (RangeCheckChipConfig<Fp, 8, 256>, TableRegistry<Fp>);
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self { values: vec![Value::unknown(); self.values.len()], num_bits: self.num_bits }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            MyCircuit::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            (config, tables): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            tables.load(&mut layouter)?;
+            let chip = RangeCheckChip::construct(config.clone());
+
+            let values = layouter.assign_region(
+                || "Witness values",
+                |mut region| {
+                    self.values
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, v)| {
+                            region.assign_advice(|| "value", config.value, offset, || *v)
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+
+            chip.range_check_batch(layouter.namespace(|| "range check"), &values, self.num_bits)
+        }
+    }
+
+    /// Two chips on their own columns, with their own lookup bit widths if `K2` differs
+    struct TwoChipsCircuit<const K2: usize, const R2: usize> {
+        values: [Value<Fp>; 2],
+    }
+
+    impl<const K2: usize, const R2: usize> Circuit<Fp> for TwoChipsCircuit<K2, R2> {
+        type Config =
+            (RangeCheckChipConfig<Fp, 8, 256>, RangeCheckChipConfig<Fp, K2, R2>, TableRegistry<Fp>);
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self { values: [Value::unknown(); 2] }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            let (a, b) = (meta.advice_column(), meta.advice_column());
+
+            let mut tables = TableRegistry::new();
+            let first = RangeCheckChip::configure(meta, a, &mut tables);
+            let second = RangeCheckChip::configure(meta, b, &mut tables);
+            (first, second, tables)
+        }
+
+        fn synthesize(
+            &self,
+            (first, second, tables): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            tables.load(&mut layouter)?;
+
+            let values = layouter.assign_region(
+                || "Witness values",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", first.value, 0, || self.values[0])?;
+                    let b = region.assign_advice(|| "b", second.value, 0, || self.values[1])?;
+                    Ok([a, b])
+                },
+            )?;
+
+            RangeCheckChip::construct(first).range_check(
+                layouter.namespace(|| "first"),
+                &values[0],
+                13,
+            )?;
+            RangeCheckChip::construct(second).range_check(
+                layouter.namespace(|| "second"),
+                &values[1],
+                13,
+            )
+        }
+    }
+
+    #[test]
+    fn test_range_check_zero_bits() {
+        let k = 9;
+
+        // only 0 fits in 0 bits, with or without batching
+        for (value, ok) in [(0, true), (1, false)] {
+            let circuit = MyCircuit { value: Value::known(Fp::from(value)), num_bits: 0 };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify().is_ok(), ok);
+
+            let values = vec![Value::known(Fp::zero()), Value::known(Fp::from(value))];
+            let circuit = BatchCircuit { values, num_bits: 0 };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            assert_eq!(prover.verify().is_ok(), ok);
+        }
+    }
+
+    #[test]
+    fn test_range_check_chip_strategy() {
+        type Chip = RangeCheckChip<Fp, 8, 256>;
+
+        assert_eq!(Chip::strategy(1), Strategy::Polynomial);
+        assert_eq!(Chip::strategy(2), Strategy::Polynomial);
+        assert_eq!(Chip::strategy(3), Strategy::Lookup);
+        assert_eq!(Chip::strategy(8), Strategy::Lookup);
+        assert_eq!(Chip::strategy(9), Strategy::Decomposition);
+        assert_eq!(Chip::strategy(64), Strategy::Decomposition);
+    }
+
+    #[test]
+    fn test_range_check_chip() {
+        let k = 9;
+
+        for num_bits in [1, 2, 3, 7, 8, 9, 13, 16, 61, 64] {
+            let max = Fp::from_u128((1 << num_bits) - 1);
+
+            for value in [Fp::zero(), max] {
+                let circuit = MyCircuit { value: Value::known(value), num_bits };
+                let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+                prover.assert_satisfied();
+            }
+
+            let circuit = MyCircuit { value: Value::known(max + Fp::one()), num_bits };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_range_check_chip_batch() {
+        let k = 9;
+
+        for num_bits in [1, 5, 8, 13] {
+            let max = Fp::from_u128((1 << num_bits) - 1);
+            let mut values: Vec<_> = (0..20u64)
+                .map(|i| Value::known(Fp::from(((1 << num_bits) - 1) * i / 19)))
+                .collect();
+
+            let circuit = BatchCircuit { values: values.clone(), num_bits };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            prover.assert_satisfied();
+
+            // a single value out of range fails the whole batch
+            values[7] = Value::known(max + Fp::one());
+            let circuit = BatchCircuit { values, num_bits };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    /// Indices of the fixed columns the lookups of `meta` look up into.
+    ///
+    /// The lookup arguments are private, but their table expressions are only queries of
+    /// table columns, which read `Fixed { query_index: _, column_index: i, .. }` in the
+    /// `Debug` output.
+    fn table_columns(meta: &ConstraintSystem<Fp>) -> std::collections::BTreeSet<usize> {
+        let debug = format!("{:?}", meta);
+        debug
+            .split("table_expressions: [")
+            .skip(1)
+            .flat_map(|lookup| lookup[..lookup.find(']').unwrap()].split("column_index: ").skip(1))
+            .map(|query| query[..query.find(',').unwrap()].parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_table_registry_shares_tables() {
+        // a single chip uses the 2-column tagged table
+        let mut meta = ConstraintSystem::<Fp>::default();
+        MyCircuit::configure(&mut meta);
+        assert_eq!(table_columns(&meta).len(), 2);
+
+        // a second chip looks up the same table columns
+        let mut two_chips = ConstraintSystem::<Fp>::default();
+        TwoChipsCircuit::<8, 256>::configure(&mut two_chips);
+        assert_eq!(table_columns(&two_chips), table_columns(&meta));
+
+        // while a different bit width gets its own table
+        let mut meta = ConstraintSystem::<Fp>::default();
+        TwoChipsCircuit::<4, 16>::configure(&mut meta);
+        assert_eq!(table_columns(&meta).len(), 4);
+
+        // the shared table is loaded once, any second load would fail synthesis
+        let k = 9;
+        let circuit = TwoChipsCircuit::<8, 256> {
+            values: [Value::known(Fp::from(8191)), Value::known(Fp::from(42))],
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        prover.assert_satisfied();
+
+        let circuit = TwoChipsCircuit::<4, 16> {
+            values: [Value::known(Fp::from(0)), Value::known(Fp::from(8192))],
+        };
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_range_check_chip_witness_sound() {
+        for num_bits in [2, 8, 13] {
+            let circuit = MyCircuit { value: Value::known(Fp::from(3)), num_bits };
+            assert_witness_sound(9, circuit, vec![]);
+
+            let values = vec![Value::known(Fp::from(3)); 4];
+            assert_witness_sound(9, BatchCircuit { values, num_bits }, vec![]);
+        }
+    }
+}
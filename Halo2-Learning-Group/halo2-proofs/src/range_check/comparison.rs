@@ -0,0 +1,388 @@
+use ff::PrimeFieldBits;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+
+use super::{decompose_range_check::DecomposeConfig, table::TableRegistry};
+
+/// Comparisons of n-bit unsigned integers, each returning a constrained boolean cell.
+pub trait ComparisonInstructions<F: FieldExt> {
+    /// 1 if a < b, 0 otherwise
+    fn less_than(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error>;
+
+    /// 1 if a ≤ b, 0 otherwise
+    fn less_eq(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error>;
+
+    /// 1 if a > b, 0 otherwise
+    fn greater_than(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error>;
+}
+
+/// The `LessThan.circom` trick: for a, b < 2^n, a < b iff a + 2^n - b has its bit n unset.
+///
+/// Rather than decomposing a + 2^n - b to n + 1 bits, the top bit is witnessed as lt and
+/// the rest is range-checked to n bits with `DecomposeConfig`:
+///
+/// ```text
+/// r = a - b + 2^n * lt    ∈ [0, 2^n),    lt ∈ {0, 1}
+/// ```
+///
+/// If a ≥ b only lt = 0 fits, and if a < b only lt = 1 does, any other choice wrapping
+/// around the field. Both operands are range-checked to n bits as well. The other
+/// comparisons swap the operands and optionally negate the result:
+///
+/// ```text
+/// a > b  = lt(b, a)
+/// a ≤ b  = 1 - lt(b, a)
+/// ```
+///
+/// ```text
+/// | lhs | rhs | lt |  r  | out | shift | negate | q_compare |
+/// -----------------------------------------------------------
+/// |  a  |  b  | lt |  r  | out |  2^n  |  0/1   |     1     |
+/// ```
+///
+/// followed by the decompositions of a, b and r.
+#[derive(Debug, Clone)]
+pub struct ComparisonConfig<
+    F: FieldExt + PrimeFieldBits,
+    const LOOKUP_NUM_BITS: usize,
+    const LOOKUP_RANGE: usize,
+> {
+    lhs: Column<Advice>,
+    rhs: Column<Advice>,
+    lt: Column<Advice>,
+    r: Column<Advice>,
+    out: Column<Advice>,
+    shift: Column<Fixed>,
+    negate: Column<Fixed>,
+    q_compare: Selector,
+    decompose: DecomposeConfig<F, LOOKUP_NUM_BITS, LOOKUP_RANGE>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ComparisonChip<
+    F: FieldExt + PrimeFieldBits,
+    const LOOKUP_NUM_BITS: usize,
+    const LOOKUP_RANGE: usize,
+> {
+    config: ComparisonConfig<F, LOOKUP_NUM_BITS, LOOKUP_RANGE>,
+}
+
+impl<F: FieldExt + PrimeFieldBits, const LOOKUP_NUM_BITS: usize, const LOOKUP_RANGE: usize>
+    ComparisonChip<F, LOOKUP_NUM_BITS, LOOKUP_RANGE>
+{
+    pub fn construct(config: ComparisonConfig<F, LOOKUP_NUM_BITS, LOOKUP_RANGE>) -> Self {
+        Self { config }
+    }
+
+    /// A constants column must be enabled for the decompositions.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        tables: &mut TableRegistry<F>,
+    ) -> ComparisonConfig<F, LOOKUP_NUM_BITS, LOOKUP_RANGE> {
+        let lhs = meta.advice_column();
+        let rhs = meta.advice_column();
+        let lt = meta.advice_column();
+        let r = meta.advice_column();
+        let out = meta.advice_column();
+        for column in [lhs, rhs, out] {
+            meta.enable_equality(column);
+        }
+
+        let shift = meta.fixed_column();
+        let negate = meta.fixed_column();
+
+        // the operands and r are copied into the running sum, which enables equality on r
+        let decompose = DecomposeConfig::configure(meta, r, tables);
+
+        let q_compare = meta.selector();
+
+        meta.create_gate("compare", |meta| {
+            let q_compare = meta.query_selector(q_compare);
+
+            let a = meta.query_advice(lhs, Rotation::cur());
+            let b = meta.query_advice(rhs, Rotation::cur());
+            let lt = meta.query_advice(lt, Rotation::cur());
+            let r = meta.query_advice(r, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+            let shift = meta.query_fixed(shift, Rotation::cur());
+            let negate = meta.query_fixed(negate, Rotation::cur());
+
+            let one = Expression::Constant(F::one());
+            let two = Expression::Constant(F::from(2));
+
+            Constraints::with_selector(
+                q_compare,
+                [
+                    ("boolean lt", lt.clone() * (one.clone() - lt.clone())),
+                    ("r = a - b + 2^n lt", r - (a - b + shift * lt.clone())),
+                    // out = lt, or 1 - lt when negated
+                    ("out", out - (lt.clone() + negate * (one - two * lt))),
+                ],
+            )
+        });
+
+        ComparisonConfig { lhs, rhs, lt, r, out, shift, negate, q_compare, decompose }
+    }
+
+    /// Returns lt(a, b), or its negation.
+    fn compare(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        num_bits: usize,
+        negate: bool,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        // a - b + 2^n must not wrap around the field
+        assert!(num_bits < F::NUM_BITS as usize - 1);
+
+        let shift = F::from(2).pow_vartime([num_bits as u64]);
+        // compared as field elements, the bit n of a + 2^n - b is the true result for
+        // operands in range only, which is checked below
+        let lt = a.value().zip(b.value()).map(|(a, b)| {
+            let shifted = *a + shift - *b;
+            let bit_n = shifted.to_le_bits()[num_bits];
+            F::from(!bit_n as u64)
+        });
+
+        let (r, out) = layouter.assign_region(
+            || "compare",
+            |mut region| {
+                config.q_compare.enable(&mut region, 0)?;
+
+                a.copy_advice(|| "a", &mut region, config.lhs, 0)?;
+                b.copy_advice(|| "b", &mut region, config.rhs, 0)?;
+                region.assign_fixed(|| "2^n", config.shift, 0, || Value::known(shift))?;
+                region.assign_fixed(
+                    || "negate",
+                    config.negate,
+                    0,
+                    || Value::known(F::from(negate as u64)),
+                )?;
+
+                let lt = region.assign_advice(|| "lt", config.lt, 0, || lt)?;
+
+                let r = a
+                    .value()
+                    .zip(b.value())
+                    .zip(lt.value())
+                    .map(|((a, b), lt)| *a - *b + shift * *lt);
+                let r = region.assign_advice(|| "r", config.r, 0, || r)?;
+
+                let out = lt.value().map(|lt| if negate { F::one() - *lt } else { *lt });
+                let out = region.assign_advice(|| "out", config.out, 0, || out)?;
+
+                Ok((r, out))
+            },
+        )?;
+
+        for (name, cell) in [("a", a), ("b", b), ("r", &r)] {
+            config.decompose.assign(
+                layouter.namespace(|| format!("range check {}", name)),
+                cell.clone(),
+                num_bits,
+            )?;
+        }
+
+        Ok(out)
+    }
+}
+
+impl<F: FieldExt + PrimeFieldBits, const LOOKUP_NUM_BITS: usize, const LOOKUP_RANGE: usize>
+    ComparisonInstructions<F> for ComparisonChip<F, LOOKUP_NUM_BITS, LOOKUP_RANGE>
+{
+    fn less_than(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.compare(layouter, a, b, num_bits, false)
+    }
+
+    fn less_eq(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.compare(layouter, b, a, num_bits, true)
+    }
+
+    fn greater_than(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.compare(layouter, b, a, num_bits, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use halo2_proofs::{
+        circuit::floor_planner::V1,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    use super::*;
+    use crate::dev::assert_witness_sound;
+
+    #[derive(Debug, Clone, Copy)]
+    enum Op {
+        LessThan,
+        LessEq,
+        GreaterThan,
+    }
+
+    /// Compares two private operands and exposes the result
+    #[derive(Clone)]
+    struct MyCircuit {
+        a: Value<Fp>,
+        b: Value<Fp>,
+        num_bits: usize,
+        op: Op,
+    }
+
+    impl Circuit<Fp> for MyCircuit {
+        type Config =
+            (ComparisonConfig<Fp, 8, 256>, TableRegistry<Fp>, Column<Advice>, Column<Instance>);
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self { a: Value::unknown(), b: Value::unknown(), ..self.clone() }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            let value = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(value);
+            meta.enable_equality(instance);
+
+            let mut tables = TableRegistry::new();
+            let config = ComparisonChip::configure(meta, &mut tables);
+            (config, tables, value, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, tables, value, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            tables.load(&mut layouter)?;
+            let chip = ComparisonChip::construct(config);
+
+            let (a, b) = layouter.assign_region(
+                || "Witness operands",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", value, 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", value, 1, || self.b)?;
+                    Ok((a, b))
+                },
+            )?;
+
+            let namespace = layouter.namespace(|| "compare");
+            let out = match self.op {
+                Op::LessThan => chip.less_than(namespace, &a, &b, self.num_bits)?,
+                Op::LessEq => chip.less_eq(namespace, &a, &b, self.num_bits)?,
+                Op::GreaterThan => chip.greater_than(namespace, &a, &b, self.num_bits)?,
+            };
+
+            layouter.constrain_instance(out.cell(), instance, 0)
+        }
+    }
+
+    fn run(a: Fp, b: Fp, num_bits: usize, op: Op, out: bool) -> Result<(), ()> {
+        let circuit = MyCircuit { a: Value::known(a), b: Value::known(b), num_bits, op };
+        let prover = MockProver::run(9, &circuit, vec![vec![Fp::from(out as u64)]]).unwrap();
+        prover.verify().map_err(|_| ())
+    }
+
+    #[test]
+    fn test_comparison() {
+        for num_bits in [1, 8, 13, 64, 128] {
+            let max = Fp::from(2).pow_vartime([num_bits as u64]) - Fp::one();
+
+            // equal, adjacent and maximal operands
+            for (a, b) in [
+                (Fp::zero(), Fp::zero()),
+                (max, max),
+                (Fp::zero(), Fp::one()),
+                (max - Fp::one(), max),
+                (Fp::zero(), max),
+            ] {
+                let lt = a != b;
+
+                for (x, y, flipped) in [(a, b, false), (b, a, true)] {
+                    let lt = lt && !flipped;
+                    let gt = a != b && flipped;
+                    let le = !gt;
+
+                    for (op, out) in [(Op::LessThan, lt), (Op::LessEq, le), (Op::GreaterThan, gt)] {
+                        assert_eq!(run(x, y, num_bits, op, out), Ok(()), "{:?}", op);
+                        assert!(run(x, y, num_bits, op, !out).is_err());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_comparison_out_of_range() {
+        let num_bits = 8;
+
+        // 256 < 1 as field elements wrapping to 8 bits, whatever the claimed result
+        for out in [false, true] {
+            assert!(run(Fp::from(256), Fp::one(), num_bits, Op::LessThan, out).is_err());
+            assert!(run(Fp::one(), Fp::from(256), num_bits, Op::LessThan, out).is_err());
+            assert!(run(-Fp::one(), Fp::zero(), num_bits, Op::LessEq, out).is_err());
+        }
+    }
+
+    #[test]
+    fn test_comparison_witness_sound() {
+        for op in [Op::LessThan, Op::LessEq, Op::GreaterThan] {
+            let circuit = MyCircuit {
+                a: Value::known(Fp::from(3)),
+                b: Value::known(Fp::from(7)),
+                num_bits: 13,
+                op,
+            };
+            let out = !matches!(op, Op::GreaterThan);
+            assert_witness_sound(9, circuit, vec![vec![Fp::from(out as u64)]]);
+        }
+    }
+}
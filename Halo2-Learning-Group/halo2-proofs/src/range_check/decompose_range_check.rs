@@ -3,70 +3,95 @@ use std::marker::PhantomData;
 use ff::PrimeFieldBits;
 use halo2_proofs::{arithmetic::FieldExt, circuit::*, plonk::*, poly::Rotation};
 
-mod table;
-use table::RangeTableConfig;
+use super::table::{RangeTableConfig, TableRegistry};
+
 /// This gadget range-constrains an element witnessed in the circuit to be N bits.
 ///
 /// Internally, this gadget uses the `range_check` helper, which provides a K-bit
 /// lookup table.
 ///
 /// Given an element `value`, we use a running sum to break it into K-bit chunks.
-/// Assume for now that N | K, and define C = N / K.
+/// Define C = floor(N / K) the number of full chunks and r = N mod K.
 ///
-///     value = [b_0, b_1, ..., b_{N-1}]   (little-endian)
-///           = c_0 + 2^K * c_1  + 2^{2K} * c_2 + ... + 2^{(C-1)K} * c_{C-1}
+/// ```text
+/// value = [b_0, b_1, ..., b_{N-1}]   (little-endian)
+///       = c_0 + 2^K * c_1  + 2^{2K} * c_2 + ... + 2^{(C-1)K} * c_{C-1}
+/// ```
 ///
-/// Initialise the running sum at
-///                                 value = z_0.
+/// Initialise the running sum at value = z_0.
 ///
 /// Consequent terms of the running sum are z_{i+1} = (z_i - c_i) * 2^{-K}:
 ///
-///                           z_1 = (z_0 - c_0) * 2^{-K}
-///                           z_2 = (z_1 - c_1) * 2^{-K}
-///                              ...
-///                       z_{C-1} = c_{C-1}
-///                           z_C = (z_{C-1} - c_{C-1}) * 2^{-K}
-///                               = 0
+/// ```text
+///                       z_1 = (z_0 - c_0) * 2^{-K}
+///                       z_2 = (z_1 - c_1) * 2^{-K}
+///                          ...
+///                   z_{C-1} = c_{C-1}
+///                       z_C = (z_{C-1} - c_{C-1}) * 2^{-K}
+///                           = 0
+/// ```
 ///
 /// One configuration for this gadget could look like:
 ///
-///     | running_sum |  q_decompose  |  table_value  |
-///     -----------------------------------------------
-///     |     z_0     |       1       |       0       |
-///     |     z_1     |       1       |       1       |
-///     |     ...     |      ...      |      ...      |
-///     |   z_{C-1}   |       1       |      ...      |
-///     |     z_C     |       0       |      ...      |
+/// ```text
+/// | running_sum |  chunk  |  q_decompose  |  q_partial  |  partial_tag  |  partial_shift  |
+/// ---------------------------------------------------------------------------------------
+/// |     z_0     |   c_0   |       1       |      0      |               |                 |
+/// |     z_1     |   c_1   |       1       |      0      |               |                 |
+/// |     ...     |   ...   |      ...      |     ...     |               |                 |
+/// |   z_{C-1}   | c_{C-1} |       1       |      0      |               |                 |
+/// |     z_C     |   c_C   |       0       |      1      |     r + 1     |       2^r       |
+/// ```
+///
+/// The chunks are witnessed in their own column and tied to the running sum by a gate, so
+/// they can be copied out by other gadgets.
 ///
-/// Stretch task: use the tagged lookup table to constrain arbitrary bitlengths
-/// (even non-multiples of K)
+/// When K does not divide N, z_C is not constrained to 0 but is the last, r-bit chunk.
+/// The tagged table holds the values of exactly `num_bits` bits, so z_C < 2^r is checked by
+/// looking up (r + 1, z_C + 2^r): the shifted value has exactly r + 1 bits iff z_C < 2^r.
+/// Outside of that row the lookup defaults to (1, 0), which is in the table.
 
 #[derive(Debug, Clone)]
-struct DecomposeConfig<
+pub struct DecomposeConfig<
     F: FieldExt + PrimeFieldBits,
     const LOOKUP_NUM_BITS: usize,
     const LOOKUP_RANGE: usize,
 > {
     // You'll need an advice column to witness your running sum;
-    running_sum: Column<Advice>,
+    pub(crate) running_sum: Column<Advice>,
+    // The chunks c_i, equal to z_i - z_{i+1} * 2^K
+    chunk: Column<Advice>,
     // A selector to constrain the running sum;
     q_decompose: Selector,
-    // And of course, the K-bit lookup table
-    table: RangeTableConfig<F, LOOKUP_NUM_BITS, LOOKUP_RANGE>,
+    // A selector for the last, partial chunk, along with its bit length plus 1 and 2^r
+    q_partial: Selector,
+    partial_tag: Column<Fixed>,
+    partial_shift: Column<Fixed>,
+    // A selector for values of exactly K bits checked on a single row, see `assign_batch`
+    q_full: Selector,
     _marker: PhantomData<F>,
 }
 
 impl<F: FieldExt + PrimeFieldBits, const LOOKUP_NUM_BITS: usize, const LOOKUP_RANGE: usize>
     DecomposeConfig<F, LOOKUP_NUM_BITS, LOOKUP_RANGE>
 {
-    fn configure(meta: &mut ConstraintSystem<F>, running_sum: Column<Advice>) -> Self {
+    /// The tagged K-bit table is taken from `tables`, which must be loaded by the circuit.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        running_sum: Column<Advice>,
+        tables: &mut TableRegistry<F>,
+    ) -> Self {
         // Create the needed columns and internal configs.
         let q_decompose = meta.complex_selector();
-        let table = RangeTableConfig::configure(meta);
+        // And of course, the K-bit lookup table
+        let table = RangeTableConfig::<F, LOOKUP_NUM_BITS, LOOKUP_RANGE>::configure(meta, tables);
 
         //we're allowed to copy things to and from this column
         meta.enable_equality(running_sum);
 
+        let chunk = meta.advice_column();
+        meta.enable_equality(chunk);
+
         // Range-constrain each K-bit chunk `c_i = z_i - z_{i+1} * 2^K` derived from the running sum.
         meta.lookup(|meta| {
             //get selector value
@@ -88,67 +113,260 @@ impl<F: FieldExt + PrimeFieldBits, const LOOKUP_NUM_BITS: usize, const LOOKUP_RA
             vec![(q_decompose * chunk + not_q_decompose * default_chunk, table.value)]
         });
 
-        Self { running_sum, q_decompose, table, _marker: PhantomData }
+        let q_partial = meta.complex_selector();
+        let partial_tag = meta.fixed_column();
+        let partial_shift = meta.fixed_column();
+
+        // Range-constrain the partial chunk z_C to r bits, with the tagged table.
+        meta.lookup(|meta| {
+            let q_partial = meta.query_selector(q_partial);
+            let z_c = meta.query_advice(running_sum, Rotation::cur());
+            let tag = meta.query_fixed(partial_tag, Rotation::cur());
+            let shift = meta.query_fixed(partial_shift, Rotation::cur());
+
+            // Lookup default value (1, 0) when q_partial = 0
+            let not_q_partial = Expression::Constant(F::one()) - q_partial.clone();
+
+            vec![
+                (q_partial.clone() * tag + not_q_partial, table.num_bits),
+                (q_partial * (z_c + shift), table.value),
+            ]
+        });
+
+        // Range-constrain a whole value to K bits, without running sum.
+        let q_full = meta.complex_selector();
+        meta.lookup(|meta| {
+            let q_full = meta.query_selector(q_full);
+            let value = meta.query_advice(running_sum, Rotation::cur());
+
+            vec![(q_full * value, table.value)]
+        });
+
+        meta.create_gate("chunk", |meta| {
+            let q_decompose = meta.query_selector(q_decompose);
+
+            let z_cur = meta.query_advice(running_sum, Rotation::cur());
+            let z_next = meta.query_advice(running_sum, Rotation::next());
+            let c = meta.query_advice(chunk, Rotation::cur());
+
+            vec![q_decompose * (c - (z_cur - z_next * F::from(1u64 << LOOKUP_NUM_BITS)))]
+        });
+
+        // the partial chunk is what is left of the running sum
+        meta.create_gate("partial chunk", |meta| {
+            let q_partial = meta.query_selector(q_partial);
+
+            let z_c = meta.query_advice(running_sum, Rotation::cur());
+            let c = meta.query_advice(chunk, Rotation::cur());
+
+            vec![q_partial * (c - z_c)]
+        });
+
+        Self {
+            running_sum,
+            chunk,
+            q_decompose,
+            q_partial,
+            partial_tag,
+            partial_shift,
+            q_full,
+            _marker: PhantomData,
+        }
     }
 
-    fn assign(
+    /// Range-checks `value` to `num_bits` bits and returns its decomposition.
+    pub fn assign<V>(
         &self,
         mut layouter: impl Layouter<F>,
-        value: AssignedCell<Assigned<F>, F>,
+        value: AssignedCell<V, F>,
         num_bits: usize,
-    ) -> Result<(), Error> {
-        //num_bits must be a multiple of k
-        assert_eq!(num_bits % LOOKUP_NUM_BITS, 0);
+    ) -> Result<Decomposition<F>, Error>
+    where
+        for<'v> Assigned<F>: From<&'v V>,
+    {
+        let num_chunks = num_bits / LOOKUP_NUM_BITS;
+        let partial_bits = num_bits % LOOKUP_NUM_BITS;
         layouter.assign_region(
             || "decompose value",
             |mut region| {
                 let mut offset = 0;
                 // 0. Copy in the witnessed `value`
-                let mut z = value.copy_advice(
+                let value_assigned = value.value().map(Assigned::from);
+                let z_0 = region.assign_advice::<_, Assigned<F>, _, _>(
                     || "Copy in value for decomposition",
-                    &mut region,
                     self.running_sum,
                     offset,
+                    || value_assigned,
                 )?;
+                region.constrain_equal(value.cell(), z_0.cell())?;
+                let mut zs = vec![z_0];
 
                 offset += 1;
 
                 // 1. Compute the interstitial running sum values {z_0, ..., z_C}}
-                let running_sum: Vec<_> = value
-                    .value()
-                    .map(|&v| compute_running_sum::<_, LOOKUP_NUM_BITS>(v, num_bits))
-                    .transpose_vec(num_bits / LOOKUP_NUM_BITS);
+                let running_sum: Vec<_> = value_assigned
+                    .map(|v| compute_running_sum::<_, LOOKUP_NUM_BITS>(v, num_bits))
+                    .transpose_vec(num_chunks);
 
                 // 2. Assign the running sum values
                 for z_i in running_sum.into_iter() {
-                    z = region.assign_advice(
+                    zs.push(region.assign_advice::<_, Assigned<F>, _, _>(
                         || format!("assign z_{:?}", offset),
                         self.running_sum,
                         offset,
                         || z_i,
-                    )?;
+                    )?);
                     offset += 1;
                 }
 
-                // 3. Make sure to enable the relevant selector on each row of the running sum
+                // 3. Witness the chunks, the partial one being z_C itself
+                let mut chunks = vec![];
+                for offset in 0..num_chunks {
+                    let c = zs[offset]
+                        .value()
+                        .zip(zs[offset + 1].value())
+                        .map(|(z_cur, z_next)| *z_cur - *z_next * F::from(1u64 << LOOKUP_NUM_BITS));
+                    chunks.push(region.assign_advice::<_, Assigned<F>, _, _>(
+                        || format!("c_{:?}", offset),
+                        self.chunk,
+                        offset,
+                        || c,
+                    )?);
+                }
+                if partial_bits != 0 {
+                    chunks.push(zs[num_chunks].copy_advice(
+                        || format!("c_{:?}", num_chunks),
+                        &mut region,
+                        self.chunk,
+                        num_chunks,
+                    )?);
+                }
+
+                // 4. Make sure to enable the relevant selector on each row of the running sum
                 //    (but not on the row where z_C is witnessed)
-                for offset in 0..(num_bits / LOOKUP_NUM_BITS) {
+                for offset in 0..num_chunks {
                     self.q_decompose.enable(&mut region, offset)?;
                 }
 
-                // 4. Constrain the final running sum `z_C` to be 0.
-                region.constrain_constant(z.cell(), F::zero())
+                // 5. Constrain the final running sum `z_C` to be 0, or to fit in the remaining bits.
+                if partial_bits == 0 {
+                    region.constrain_constant(zs.last().unwrap().cell(), F::zero())?;
+                } else {
+                    self.q_partial.enable(&mut region, num_chunks)?;
+                    region.assign_fixed::<_, F, _, _>(
+                        || "r + 1",
+                        self.partial_tag,
+                        num_chunks,
+                        || Value::known(F::from(partial_bits as u64 + 1)),
+                    )?;
+                    region.assign_fixed::<_, F, _, _>(
+                        || "2^r",
+                        self.partial_shift,
+                        num_chunks,
+                        || Value::known(F::from(1 << partial_bits)),
+                    )?;
+                }
+
+                Ok(Decomposition { running_sum: zs, chunks })
+            },
+        )
+    }
+
+    /// Range-checks every value of `values` to `num_bits` ≤ K bits in a single region, one
+    /// row per value, and returns the copies.
+    ///
+    /// Each row is the running sum of a value without full chunk: a value shorter than K is
+    /// its own partial chunk, checked with the tagged lookup, while a K-bit value is looked
+    /// up directly.
+    ///
+    /// ```text
+    /// | running_sum |  chunk  |  q_partial  |  q_full  |  partial_tag  |  partial_shift  |
+    /// ----------------------------------------------------------------------------------
+    /// |     v_0     |   v_0   |      1      |    0     |     n + 1     |       2^n       |
+    /// |     v_1     |   v_1   |      1      |    0     |     n + 1     |       2^n       |
+    /// |     ...     |   ...   |     ...     |   ...    |      ...      |       ...       |
+    /// ```
+    pub fn assign_batch<V>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[AssignedCell<V, F>],
+        num_bits: usize,
+    ) -> Result<Vec<AssignedCell<Assigned<F>, F>>, Error>
+    where
+        for<'v> Assigned<F>: From<&'v V>,
+    {
+        assert!(
+            (1..=LOOKUP_NUM_BITS).contains(&num_bits),
+            "batched range checks are limited to 1..={} bits, got {}",
+            LOOKUP_NUM_BITS,
+            num_bits
+        );
+
+        layouter.assign_region(
+            || "batched range check",
+            |mut region| {
+                let mut copies = Vec::with_capacity(values.len());
+
+                for (offset, value) in values.iter().enumerate() {
+                    let z_0 = region.assign_advice::<_, Assigned<F>, _, _>(
+                        || format!("copy value {}", offset),
+                        self.running_sum,
+                        offset,
+                        || value.value().map(Assigned::from),
+                    )?;
+                    region.constrain_equal(value.cell(), z_0.cell())?;
+
+                    if num_bits == LOOKUP_NUM_BITS {
+                        self.q_full.enable(&mut region, offset)?;
+                    } else {
+                        // tied to the value by the "partial chunk" gate
+                        region.assign_advice::<_, Assigned<F>, _, _>(
+                            || "partial chunk",
+                            self.chunk,
+                            offset,
+                            || z_0.value().copied(),
+                        )?;
+
+                        self.q_partial.enable(&mut region, offset)?;
+                        region.assign_fixed::<_, F, _, _>(
+                            || "n + 1",
+                            self.partial_tag,
+                            offset,
+                            || Value::known(F::from(num_bits as u64 + 1)),
+                        )?;
+                        region.assign_fixed::<_, F, _, _>(
+                            || "2^n",
+                            self.partial_shift,
+                            offset,
+                            || Value::known(F::from(1 << num_bits)),
+                        )?;
+                    }
+
+                    copies.push(z_0);
+                }
+
+                Ok(copies)
             },
         )
     }
 }
 
-fn lebs2ip(bits: &[bool]) -> u64 {
+/// Cells produced by `DecomposeConfig::assign`.
+#[derive(Debug, Clone)]
+pub struct Decomposition<F: FieldExt> {
+    /// The running sum {z_0, ..., z_C}, z_0 being a copy of the value and z_C being 0 if
+    /// `num_bits` is a multiple of K, the top `num_bits mod K` bits of the value otherwise
+    pub running_sum: Vec<AssignedCell<Assigned<F>, F>>,
+    /// The little-endian chunks {c_0, ..., c_{C-1}}, followed by the r-bit chunk if r > 0
+    pub chunks: Vec<AssignedCell<Assigned<F>, F>>,
+}
+
+pub(crate) fn lebs2ip(bits: &[bool]) -> u64 {
     assert!(bits.len() <= 64);
     bits.iter().enumerate().fold(0u64, |acc, (i, b)| acc + if *b { 1 << i } else { 0 })
 }
 
-// Function to compute the interstitial running sum values {z_1, ..., z_C}}
+// Function to compute the interstitial running sum values {z_1, ..., z_C}}, for the full chunks
 fn compute_running_sum<F: FieldExt + PrimeFieldBits, const LOOKUP_NUM_BITS: usize>(
     value: Assigned<F>,
     num_bits: usize,
@@ -158,7 +376,7 @@ fn compute_running_sum<F: FieldExt + PrimeFieldBits, const LOOKUP_NUM_BITS: usiz
 
     let value: Vec<_> = value.evaluate().to_le_bits().iter().by_vals().take(num_bits).collect();
 
-    for chunk in value.chunks(LOOKUP_NUM_BITS) {
+    for chunk in value.chunks_exact(LOOKUP_NUM_BITS) {
         let chunk = Assigned::from(F::from(lebs2ip(chunk)));
         z = (z - chunk) * Assigned::from(F::from(1u64 << LOOKUP_NUM_BITS)).invert();
         running_sum.push(z);
@@ -173,6 +391,7 @@ mod tests {
     use halo2_proofs::{circuit::floor_planner::V1, dev::MockProver, pasta::Fp};
 
     use super::*;
+    use crate::dev::assert_witness_sound;
 
     struct MyCircuit<F: FieldExt, const NUM_BITS: usize, const RANGE: usize> {
         value: Value<Assigned<F>>,
@@ -182,7 +401,7 @@ mod tests {
     impl<F: FieldExt + PrimeFieldBits, const NUM_BITS: usize, const RANGE: usize> Circuit<F>
         for MyCircuit<F, NUM_BITS, RANGE>
     {
-        type Config = DecomposeConfig<F, NUM_BITS, RANGE>;
+        type Config = (DecomposeConfig<F, NUM_BITS, RANGE>, TableRegistry<F>);
         type FloorPlanner = V1;
 
         fn without_witnesses(&self) -> Self {
@@ -196,15 +415,16 @@ mod tests {
             meta.enable_constant(constants);
 
             let value = meta.advice_column();
-            DecomposeConfig::configure(meta, value)
+            let mut tables = TableRegistry::new();
+            (DecomposeConfig::configure(meta, value, &mut tables), tables)
         }
 
         fn synthesize(
             &self,
-            config: Self::Config,
+            (config, tables): Self::Config,
             mut layouter: impl Layouter<F>,
         ) -> Result<(), Error> {
-            config.table.load(&mut layouter)?;
+            tables.load(&mut layouter)?;
 
             // Witness the value somewhere
             let value = layouter.assign_region(
@@ -236,6 +456,124 @@ mod tests {
         prover.assert_satisfied();
     }
 
+    #[test]
+    fn test_decompose_partial_chunk() {
+        let k = 9;
+        const NUM_BITS: usize = 8;
+        const RANGE: usize = 256; // 8-bit value
+
+        // every remainder modulo 8, 13 and 61 bits among them
+        for num_bits in 1..=64 {
+            let max = Fp::from_u128((1 << num_bits) - 1);
+            let random = Fp::from(rand::random::<u64>() >> (64 - num_bits));
+
+            for value in [Fp::zero(), random, max] {
+                let value = Value::known(Assigned::from(value));
+                let circuit = MyCircuit::<Fp, NUM_BITS, RANGE> { value, num_bits };
+
+                let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+                prover.assert_satisfied();
+            }
+
+            // 2^num_bits only fails on its top chunk
+            let value = Value::known(Assigned::from(max + Fp::one()));
+            let circuit = MyCircuit::<Fp, NUM_BITS, RANGE> { value, num_bits };
+
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    /// Exposes the chunks of a private value
+    struct ChunksCircuit {
+        value: Value<Fp>,
+        num_bits: usize,
+    }
+
+    impl Circuit<Fp> for ChunksCircuit {
+        type Config =
+            (DecomposeConfig<Fp, 8, 256>, TableRegistry<Fp>, Column<Advice>, Column<Instance>);
+        type FloorPlanner = V1;
+
+        fn without_witnesses(&self) -> Self {
+            Self { value: Value::unknown(), num_bits: self.num_bits }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            let value = meta.advice_column();
+            let copies = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(copies);
+            meta.enable_equality(instance);
+
+            let mut tables = TableRegistry::new();
+            let config = DecomposeConfig::configure(meta, value, &mut tables);
+            (config, tables, copies, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, tables, copies, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            tables.load(&mut layouter)?;
+
+            let value = layouter.assign_region(
+                || "Witness value",
+                |mut region| region.assign_advice(|| "value", config.running_sum, 0, || self.value),
+            )?;
+
+            let decomposition =
+                config.assign(layouter.namespace(|| "decompose value"), value, self.num_bits)?;
+
+            // the chunks can be moved around like any other cell
+            let chunks = layouter.assign_region(
+                || "copy chunks",
+                |mut region| {
+                    decomposition
+                        .chunks
+                        .iter()
+                        .enumerate()
+                        .map(|(offset, c)| c.copy_advice(|| "chunk", &mut region, copies, offset))
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+
+            for (row, c) in chunks.iter().enumerate() {
+                layouter.constrain_instance(c.cell(), instance, row)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_decompose_chunks() {
+        let k = 9;
+        let value: u64 = rand::random();
+
+        for num_bits in [61, 64] {
+            let value = value >> (64 - num_bits);
+            let chunks: Vec<Fp> =
+                (0..num_bits).step_by(8).map(|i| Fp::from((value >> i) & 0xff)).collect();
+            // seven full chunks and a 5-bit one for 61 bits
+            assert_eq!(chunks.len(), 8);
+
+            let circuit = ChunksCircuit { value: Value::known(Fp::from(value)), num_bits };
+
+            let prover = MockProver::run(k, &circuit, vec![chunks.clone()]).unwrap();
+            prover.assert_satisfied();
+
+            let mut wrong = chunks;
+            wrong[0] += Fp::one();
+            let prover = MockProver::run(k, &circuit, vec![wrong]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
     #[cfg(feature = "dev-graph")]
     #[test]
     fn print_decompose_1() {
@@ -248,4 +586,16 @@ mod tests {
         let circuit = MyCircuit::<Fp, 8, 256> { value: Value::unknown(), num_bits: 64 };
         halo2_proofs::dev::CircuitLayout::default().render(9, &circuit, &root).unwrap();
     }
+
+    #[test]
+    fn test_decompose_witness_sound() {
+        for num_bits in [61, 64] {
+            let value: u64 = rand::random::<u64>() >> (64 - num_bits);
+            let circuit = MyCircuit::<Fp, 8, 256> {
+                value: Value::known(Assigned::from(Fp::from(value))),
+                num_bits,
+            };
+            assert_witness_sound(9, circuit, vec![]);
+        }
+    }
 }
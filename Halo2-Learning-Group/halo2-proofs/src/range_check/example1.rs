@@ -8,14 +8,14 @@ use halo2_proofs::{
 };
 
 #[derive(Debug, Clone)]
-struct RangeCheckConfig<F: FieldExt, const RANGE: usize> {
+pub(crate) struct RangeCheckConfig<F: FieldExt, const RANGE: usize> {
     value: Column<Advice>,
     q_range_check: Selector,
     _marker: PhantomData<F>,
 }
 
 impl<F: FieldExt, const RANGE: usize> RangeCheckConfig<F, RANGE> {
-    fn configure(meta: &mut ConstraintSystem<F>, value: Column<Advice>) -> Self {
+    pub(crate) fn configure(meta: &mut ConstraintSystem<F>, value: Column<Advice>) -> Self {
         let q_range_check = meta.selector();
 
         let config = Self { q_range_check, value, _marker: PhantomData };
@@ -38,11 +38,11 @@ impl<F: FieldExt, const RANGE: usize> RangeCheckConfig<F, RANGE> {
         config
     }
 
-    fn assign(
+    pub(crate) fn assign(
         &self,
         mut layouter: impl Layouter<F>,
         value: Value<Assigned<F>>,
-    ) -> Result<(), Error> {
+    ) -> Result<AssignedCell<Assigned<F>, F>, Error> {
         layouter.assign_region(
             || "Assign value",
             |mut region| {
@@ -50,9 +50,7 @@ impl<F: FieldExt, const RANGE: usize> RangeCheckConfig<F, RANGE> {
                 //enable q_range_check
                 self.q_range_check.enable(&mut region, offset)?;
 
-                region.assign_advice(|| "Assign value", self.value, offset, || value)?;
-
-                Ok(())
+                region.assign_advice(|| "Assign value", self.value, offset, || value)
             },
         )
     }
@@ -68,6 +66,7 @@ mod tests {
     };
 
     use super::*;
+    use crate::dev::assert_witness_sound;
 
     #[derive(Default)]
     struct MyCircuit<F: FieldExt, const RANGE: usize> {
@@ -129,4 +128,10 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_range_check_1_witness_sound() {
+        let circuit = MyCircuit::<Fp, 8> { value: Value::known(Fp::from(5).into()) };
+        assert_witness_sound(4, circuit, vec![]);
+    }
 }
@@ -2,7 +2,7 @@ use std::marker::PhantomData;
 
 use halo2_proofs::{
     arithmetic::FieldExt,
-    circuit::{AssignedCell, Layouter, Value},
+    circuit::{Layouter, Value},
     plonk::{Advice, Assigned, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
     poly::Rotation,
 };
@@ -10,6 +10,8 @@ use halo2_proofs::{
 mod table;
 use table::RangeTableConfig;
 
+use super::table::TableRegistry;
+
 //LOOKUP_RANGE > RANGE
 #[derive(Debug, Clone)]
 struct RangeCheckConfig<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize> {
@@ -23,13 +25,17 @@ struct RangeCheckConfig<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usi
 impl<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize>
     RangeCheckConfig<F, RANGE, LOOKUP_RANGE>
 {
-    fn configure(meta: &mut ConstraintSystem<F>, value: Column<Advice>) -> Self {
+    fn configure(
+        meta: &mut ConstraintSystem<F>,
+        value: Column<Advice>,
+        tables: &mut TableRegistry<F>,
+    ) -> Self {
         let q_range_check = meta.selector();
         //toggles the lookup argument
         //selector optimisation can hand up resulting in non-binary values, which we don't want when using these as toggle values
         let q_lookup = meta.complex_selector();
 
-        let table = RangeTableConfig::configure(meta);
+        let table = RangeTableConfig::configure(meta, tables);
 
         let config =
             Self { q_range_check, value, q_lookup, table: table.clone(), _marker: PhantomData };
@@ -67,7 +73,8 @@ impl<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize>
     ) -> Result<(), Error> {
         assert!(range <= LOOKUP_RANGE);
 
-        if range < RANGE {
+        //values below RANGE fit the expression, larger ones go through the table
+        if range <= RANGE {
             layouter.assign_region(
                 || "Assign value",
                 |mut region| {
@@ -100,14 +107,10 @@ impl<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize>
 
 #[cfg(test)]
 mod tests {
-    use halo2_proofs::{
-        circuit::floor_planner::V1,
-        dev::{FailureLocation, MockProver, VerifyFailure},
-        pasta::Fp,
-        plonk::{Any, Circuit},
-    };
+    use halo2_proofs::{circuit::floor_planner::V1, dev::MockProver, pasta::Fp, plonk::Circuit};
 
     use super::*;
+    use crate::dev::assert_witness_sound;
 
     #[derive(Default)]
     struct MyCircuit<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize> {
@@ -118,7 +121,7 @@ mod tests {
     impl<F: FieldExt, const RANGE: usize, const LOOKUP_RANGE: usize> Circuit<F>
         for MyCircuit<F, RANGE, LOOKUP_RANGE>
     {
-        type Config = RangeCheckConfig<F, RANGE, LOOKUP_RANGE>;
+        type Config = (RangeCheckConfig<F, RANGE, LOOKUP_RANGE>, TableRegistry<F>);
         type FloorPlanner = V1;
 
         fn without_witnesses(&self) -> Self {
@@ -127,15 +130,16 @@ mod tests {
 
         fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
             let value = meta.advice_column();
-            RangeCheckConfig::configure(meta, value)
+            let mut tables = TableRegistry::new();
+            (RangeCheckConfig::configure(meta, value, &mut tables), tables)
         }
 
         fn synthesize(
             &self,
-            config: Self::Config,
+            (config, tables): Self::Config,
             mut layouter: impl Layouter<F>,
         ) -> Result<(), Error> {
-            config.table.load(&mut layouter)?;
+            tables.load(&mut layouter)?;
 
             config.assign(layouter.namespace(|| "Assign value"), self.value, RANGE)?;
             config.assign(
@@ -166,23 +170,30 @@ mod tests {
             prover.assert_satisfied();
         }
 
-        // // Out-of-range `value = 8`
-        // {
-        //     let circuit = MyCircuit::<Fp, RANGE, LOOKUP_RANGE> {
-        //         value: Value::known(Fp::from(RANGE as u64).into()),
-        //     };
-        //     let prover = MockProver::run(k, &circuit, vec![]).unwrap();
-        //     assert_eq!(
-        //         prover.verify(),
-        //         Err(vec![VerifyFailure::ConstraintNotSatisfied {
-        //             constraint: ((0, "range check").into(), 0, "range check").into(),
-        //             location: FailureLocation::InRegion {
-        //                 region: (0, "Assign value").into(),
-        //                 offset: 0
-        //             },
-        //             cell_values: vec![(((Any::Advice, 0).into(), 0).into(), "0x8".to_string())]
-        //         }])
-        //     );
-        // }
+        // Out-of-range `value = 8` and `large_value = 256`
+        {
+            let circuit = MyCircuit::<Fp, RANGE, LOOKUP_RANGE> {
+                value: Value::known(Fp::from(RANGE as u64).into()),
+                large_value: Value::known(Fp::from(0).into()),
+            };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err());
+
+            let circuit = MyCircuit::<Fp, RANGE, LOOKUP_RANGE> {
+                value: Value::known(Fp::from(0).into()),
+                large_value: Value::known(Fp::from(LOOKUP_RANGE as u64).into()),
+            };
+            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_range_check_2_witness_sound() {
+        let circuit = MyCircuit::<Fp, 8, 256> {
+            value: Value::known(Fp::from(5).into()),
+            large_value: Value::known(Fp::from(200).into()),
+        };
+        assert_witness_sound(9, circuit, vec![]);
     }
 }
@@ -2,10 +2,11 @@ use std::marker::PhantomData;
 
 use halo2_proofs::{
     arithmetic::FieldExt,
-    circuit::{Layouter, Value},
-    plonk::{ConstraintSystem, Error, TableColumn},
+    plonk::{ConstraintSystem, TableColumn},
 };
 
+use crate::range_check::table::{TableKind, TableRegistry};
+
 /// A look up table of values of RANGE length
 /// e.g RANGE = 8, values =[0..255]
 ///
@@ -17,29 +18,11 @@ pub(super) struct RangeTableConfig<F: FieldExt, const RANGE: usize> {
 }
 
 impl<F: FieldExt, const RANGE: usize> RangeTableConfig<F, RANGE> {
-    pub(super) fn configure(meta: &mut ConstraintSystem<F>) -> Self {
-        let value: TableColumn = meta.lookup_table_column();
-
-        Self { value, _marker: PhantomData }
-    }
-
-    pub(super) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
-        layouter.assign_table(
-            || "load range check table",
-            |mut table| {
-                let mut offset = 0;
-                for i in 0..RANGE {
-                    table.assign_cell(
-                        || "assign cell",
-                        self.value,
-                        offset,
-                        || Value::known(F::from(i as u64)),
-                    )?;
+    pub(super) fn configure(meta: &mut ConstraintSystem<F>, tables: &mut TableRegistry<F>) -> Self {
+        // the registry holds power-of-two ranges
+        assert!(RANGE.is_power_of_two());
+        let columns = tables.columns(meta, TableKind::Range, RANGE.trailing_zeros() as usize);
 
-                    offset += 1;
-                }
-                Ok(())
-            },
-        )
+        Self { value: columns[0], _marker: PhantomData }
     }
 }
@@ -7,8 +7,7 @@ use halo2_proofs::{
     poly::Rotation,
 };
 
-mod table;
-use table::RangeTableConfig;
+use super::table::{RangeTableConfig, TableRegistry};
 
 //LOOKUP_RANGE > RANGE
 #[derive(Debug, Clone)]
@@ -26,13 +25,14 @@ impl<F: FieldExt, const NUM_BITS: usize, const RANGE: usize> RangeCheckConfig<F,
         meta: &mut ConstraintSystem<F>,
         value: Column<Advice>,
         num_bits: Column<Advice>,
+        tables: &mut TableRegistry<F>,
     ) -> Self {
         let q_range_check = meta.selector();
         //toggles the lookup argument
         //selector optimisation can hand up resulting in non-binary values, which we don't want when using these as toggle values
         let q_lookup = meta.complex_selector();
 
-        let table = RangeTableConfig::configure(meta);
+        let table = RangeTableConfig::configure(meta, tables);
 
         let config = Self {
             value,
@@ -100,6 +100,7 @@ mod tests {
     use halo2_proofs::{circuit::floor_planner::V1, dev::MockProver, pasta::Fp, plonk::Circuit};
 
     use super::*;
+    use crate::dev::assert_witness_sound;
 
     #[derive(Default)]
     struct MyCircuit<F: FieldExt, const NUM_BITS: usize, const RANGE: usize> {
@@ -110,7 +111,7 @@ mod tests {
     impl<F: FieldExt, const NUM_BITS: usize, const RANGE: usize> Circuit<F>
         for MyCircuit<F, NUM_BITS, RANGE>
     {
-        type Config = RangeCheckConfig<F, NUM_BITS, RANGE>;
+        type Config = (RangeCheckConfig<F, NUM_BITS, RANGE>, TableRegistry<F>);
         type FloorPlanner = V1;
 
         fn without_witnesses(&self) -> Self {
@@ -120,15 +121,16 @@ mod tests {
         fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
             let value = meta.advice_column();
             let num_bits = meta.advice_column();
-            RangeCheckConfig::configure(meta, value, num_bits)
+            let mut tables = TableRegistry::new();
+            (RangeCheckConfig::configure(meta, value, num_bits, &mut tables), tables)
         }
 
         fn synthesize(
             &self,
-            config: Self::Config,
+            (config, tables): Self::Config,
             mut layouter: impl Layouter<F>,
         ) -> Result<(), Error> {
-            config.table.load(&mut layouter)?;
+            tables.load(&mut layouter)?;
 
             // config.assign(layouter.namespace(|| "Assign value"), self.value, Value::known(F::zero().into()), RANGE)?;
             config.assign(layouter.namespace(|| "Assign value"), self.value, self.num_bits)?;
@@ -156,4 +158,13 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_range_check_3_witness_sound() {
+        let circuit = MyCircuit::<Fp, 8, 256> {
+            value: Value::known(Fp::from(100).into()),
+            num_bits: Value::known(7),
+        };
+        assert_witness_sound(9, circuit, vec![]);
+    }
 }
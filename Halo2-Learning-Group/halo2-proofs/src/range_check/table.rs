@@ -0,0 +1,175 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, Value},
+    plonk::{ConstraintSystem, Error, TableColumn},
+};
+
+/// The lookup tables shared by the range-checking chips.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableKind {
+    /// The values [0, 2^num_bits), in a single column
+    Range,
+    /// The values [0, 2^num_bits), each tagged with its exact bit length (0 being tagged 1)
+    TaggedRange,
+}
+
+#[derive(Debug, Clone)]
+struct RegisteredTable {
+    kind: TableKind,
+    num_bits: usize,
+    columns: Vec<TableColumn>,
+}
+
+/// Lookup tables of a circuit, deduplicated by (kind, bit width).
+///
+/// Chips ask the registry for their table columns in `configure`, so chips needing the same
+/// table share its columns. The circuit keeps the registry in its config and loads every
+/// table once at the start of synthesis:
+///
+/// ```text
+/// configure  : tables.columns(meta, kind, num_bits)  -> existing or new columns
+/// synthesize : tables.load(&mut layouter)            -> one assign_table per table
+/// ```
+#[derive(Debug, Clone)]
+pub struct TableRegistry<F: FieldExt> {
+    tables: Vec<RegisteredTable>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Default for TableRegistry<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: FieldExt> TableRegistry<F> {
+    pub fn new() -> Self {
+        Self { tables: vec![], _marker: PhantomData }
+    }
+
+    /// Returns the columns of the (kind, num_bits) table, allocating them on first use.
+    pub fn columns(
+        &mut self,
+        meta: &mut ConstraintSystem<F>,
+        kind: TableKind,
+        num_bits: usize,
+    ) -> Vec<TableColumn> {
+        if let Some(table) =
+            self.tables.iter().find(|table| table.kind == kind && table.num_bits == num_bits)
+        {
+            return table.columns.clone();
+        }
+
+        let num_columns = match kind {
+            TableKind::Range => 1,
+            TableKind::TaggedRange => 2,
+        };
+        let columns: Vec<_> = (0..num_columns).map(|_| meta.lookup_table_column()).collect();
+
+        self.tables.push(RegisteredTable { kind, num_bits, columns: columns.clone() });
+        columns
+    }
+
+    /// Number of distinct tables registered so far.
+    pub fn num_tables(&self) -> usize {
+        self.tables.len()
+    }
+
+    /// Loads every registered table, once per circuit.
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        for table in self.tables.iter() {
+            match table.kind {
+                TableKind::Range => load_range(layouter, table.columns[0], table.num_bits)?,
+                TableKind::TaggedRange => {
+                    load_tagged_range(layouter, table.columns[0], table.columns[1], table.num_bits)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn load_range<F: FieldExt>(
+    layouter: &mut impl Layouter<F>,
+    value: TableColumn,
+    num_bits: usize,
+) -> Result<(), Error> {
+    layouter.assign_table(
+        || format!("load {}-bit range table", num_bits),
+        |mut table| {
+            for (offset, i) in (0..1 << num_bits).enumerate() {
+                table.assign_cell(
+                    || "assign cell",
+                    value,
+                    offset,
+                    || Value::known(F::from(i as u64)),
+                )?;
+            }
+            Ok(())
+        },
+    )
+}
+
+fn load_tagged_range<F: FieldExt>(
+    layouter: &mut impl Layouter<F>,
+    tag: TableColumn,
+    value: TableColumn,
+    max_bits: usize,
+) -> Result<(), Error> {
+    layouter.assign_table(
+        || format!("load {}-bit tagged range table", max_bits),
+        |mut table| {
+            let mut offset = 0;
+
+            table.assign_cell(|| "assign num_bits = 1", tag, offset, || Value::known(F::one()))?;
+
+            table.assign_cell(|| "assign value = 0", value, offset, || Value::known(F::zero()))?;
+
+            offset += 1;
+
+            for num_bits in 1..=max_bits {
+                for i in 1 << (num_bits - 1)..(1 << num_bits) {
+                    table.assign_cell(
+                        || "assign num_bits",
+                        tag,
+                        offset,
+                        || Value::known(F::from(num_bits as u64)),
+                    )?;
+
+                    table.assign_cell(
+                        || "assign value",
+                        value,
+                        offset,
+                        || Value::known(F::from(i as u64)),
+                    )?;
+
+                    offset += 1;
+                }
+            }
+
+            Ok(())
+        },
+    )
+}
+
+/// A look up table of values of RANGE length
+/// e.g RANGE = 8, values =[0..255]
+/// this table is tagged by an index k, where k is the number of bits of the value in the 'value' column.
+///
+#[derive(Debug, Clone)]
+pub(crate) struct RangeTableConfig<F: FieldExt, const NUM_BITS: usize, const RANGE: usize> {
+    pub(crate) num_bits: TableColumn,
+    pub(crate) value: TableColumn,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const NUM_BITS: usize, const RANGE: usize> RangeTableConfig<F, NUM_BITS, RANGE> {
+    pub(crate) fn configure(meta: &mut ConstraintSystem<F>, tables: &mut TableRegistry<F>) -> Self {
+        assert_eq!(1 << NUM_BITS, RANGE);
+        let columns = tables.columns(meta, TableKind::TaggedRange, NUM_BITS);
+
+        Self { num_bits: columns[0], value: columns[1], _marker: PhantomData }
+    }
+}